@@ -0,0 +1,72 @@
+// compares evaluating a formula row by row with Formula::evaluate against the compiled evaluator.
+// run with `cargo bench`; there is no harness, each case is timed with std::time::Instant.
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use truth_value_table::formula::{Formula, Interpretations, RowOrder, SecondaryFuncName};
+
+// (A1 → A2) ∧ (A2 → A3) ∧ ... ∧ (An → A1), true only when all the letters agree
+fn cycle(letters: &[char]) -> Formula {
+    let implication = |i: usize| Formula::SecondaryFunc {
+        name: SecondaryFuncName::Implicature,
+        lhs: Box::new(Formula::Letter(letters[i])),
+        rhs: Box::new(Formula::Letter(letters[(i + 1) % letters.len()])),
+    };
+    (1..letters.len()).fold(implication(0), |lhs, i| Formula::SecondaryFunc {
+        name: SecondaryFuncName::Conjunction,
+        lhs: Box::new(lhs),
+        rhs: Box::new(implication(i)),
+    })
+}
+
+fn count_models_by_evaluate(f: &Formula) -> u128 {
+    let letters: Vec<_> = f.free_letters().into_iter().collect();
+    let mut interpretation = HashMap::new();
+    let mut models = 0;
+    for values in Interpretations::new(letters.len(), RowOrder::FalseFirst) {
+        interpretation.extend(letters.iter().copied().zip(values));
+        models += f.evaluate(&interpretation).is_true() as u128;
+    }
+    models
+}
+
+fn time<T>(f: impl Fn() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let result = black_box(f());
+    (result, start.elapsed())
+}
+
+fn main() {
+    let letters: Vec<_> = ('A'..='Z').collect();
+    for n in [8, 12, 16, 20] {
+        let f = cycle(&letters[..n]);
+        let (expected, evaluate) = time(|| count_models_by_evaluate(&f));
+        let compiled = f.compile();
+        let (models, bitwise) = time(|| compiled.count_models());
+        assert_eq!(models, expected);
+        println!(
+            "count models, {:>2} letters: evaluate {:>10.3?}, compiled {:>10.3?}, {:>6.0}x",
+            n,
+            evaluate,
+            bitwise,
+            evaluate.as_secs_f64() / bitwise.as_secs_f64().max(1e-9)
+        );
+    }
+
+    let f = cycle(&letters[..24]);
+    let (_, tautology) = time(|| f.is_tautology());
+    println!("is tautology, 24 letters: compiled {:>10.3?}", tautology);
+
+    let compiled = cycle(&letters[..26]).compile();
+    let (expected, one) = time(|| compiled.count_models_with(1));
+    let threads = truth_value_table::parallel::threads(0);
+    let (models, all) = time(|| compiled.count_models_with(threads));
+    assert_eq!(models, expected);
+    println!(
+        "count models, 26 letters: 1 thread {:>10.3?}, {} threads {:>10.3?}, {:>4.1}x",
+        one,
+        threads,
+        all,
+        one.as_secs_f64() / all.as_secs_f64().max(1e-9)
+    );
+}
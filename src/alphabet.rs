@@ -1,4 +1,5 @@
-#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
 pub enum SecondaryFuncName {
     Conjunction,
     Disjunction,
@@ -32,35 +33,144 @@ pub enum Alphabet {
     Implicature,
     Equivalence,
     SecondaryFunc(SecondaryFuncName),
+    Forall(char),
+    Exists(char),
+}
+
+// the glyphs formulas are written with
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum SymbolSet {
+    #[default]
+    Unicode,
+    Ascii,
+    Words,
+    Latex, // for LaTeX math mode
+}
+
+impl SymbolSet {
+    // ⊤, ⊥, ¬, ∧, ∨, →, ↔, ∀ and ∃ in this set.
+    // the quantifiers are followed by the letter and a dot.
+    fn glyphs(&self) -> [&'static str; 9] {
+        match self {
+            Self::Unicode => [
+                "⊤", // \u{22A4}
+                "⊥", // \u{22A5}
+                "¬", // \u{00AC}
+                "∧", // \u{2227}
+                "∨", // \u{2228}
+                "→", // \u{2192}
+                "↔", // \u{2194}
+                "∀", // \u{2200}
+                "∃", // \u{2203}
+            ],
+            Self::Ascii => ["true", "false", "~", "&", "|", "->", "<->", "forall ", "exists "],
+            Self::Words => ["true", "false", "not", "and", "or", "implies", "iff", "forall ", "exists "],
+            Self::Latex => ["\\top", "\\bot", "\\neg", "\\land", "\\lor", "\\to", "\\leftrightarrow", "\\forall ", "\\exists "],
+        }
+    }
+
+    // separates the premises of an argument from its conclusion
+    pub fn turnstile(&self) -> &'static str {
+        match self {
+            Self::Unicode => "⊢",
+            Self::Ascii => "|-",
+            Self::Words => "therefore",
+            Self::Latex => "\\vdash",
+        }
+    }
+
+    // heads the column marking rows where formulas differ
+    pub fn not_equal(&self) -> &'static str {
+        match self {
+            Self::Unicode => "≠",
+            Self::Ascii => "!=",
+            Self::Words => "differ",
+            Self::Latex => "\\neq",
+        }
+    }
+
+    // separates a subformula from what a law rewrites it to
+    pub fn arrow(&self) -> &'static str {
+        match self {
+            Self::Unicode => "⟹",
+            Self::Ascii => "=>",
+            Self::Words => "becomes",
+            Self::Latex => "\\Longrightarrow",
+        }
+    }
+}
+
+impl std::convert::TryFrom<&str> for SymbolSet {
+    type Error = &'static str;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "unicode" => Ok( Self::Unicode ),
+            "ascii" => Ok( Self::Ascii ),
+            "words" => Ok( Self::Words ),
+            "latex" => Ok( Self::Latex ),
+            _ => Err("Not a symbol set")
+        }
+    }
 }
 
 impl std::fmt::Display for Alphabet {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.symbol(SymbolSet::Unicode))
+    }
+}
+
+impl Alphabet {
+    pub fn symbol(&self, symbols: SymbolSet) -> String {
+        let [top, bot, neg, and, or, to, iff, forall, exists] = symbols.glyphs();
         match self {
-            Self::OpenBracket   => write!(f, "("),
-            Self::CloseBracket  => write!(f, ")"),
-            Self::Letter(c)     => write!(f, "{}", c),
-            Self::True          => write!(f, "⊤"), // \u{22A4}
-            Self::False         => write!(f, "⊥"), // \u{22A5}
-            Self::Negation      => write!(f, "¬"), // \u{00AC}
-            Self::Conjunction   => write!(f, "∧"), // \u{2227}
-            Self::Disjunction   => write!(f, "∨"), // \u{2228}
-            Self::Implicature   => write!(f, "→"), // \u{2192}
-            Self::Equivalence   => write!(f, "↔"), // \u{2194}
+            Self::OpenBracket   => String::from("("),
+            Self::CloseBracket  => String::from(")"),
+            Self::Letter(c)     => c.to_string(),
+            Self::True          => String::from(top),
+            Self::False         => String::from(bot),
+            Self::Negation      => String::from(neg),
+            Self::Conjunction   => String::from(and),
+            Self::Disjunction   => String::from(or),
+            Self::Implicature   => String::from(to),
+            Self::Equivalence   => String::from(iff),
             Self::SecondaryFunc(name) => {
-                let s =
                 match name {
-                    SecondaryFuncName::Conjunction => "∧", // \u{2227}
-                    SecondaryFuncName::Disjunction => "∨", // \u{2228}
-                    SecondaryFuncName::Implicature => "→", // \u{2192}
-                    SecondaryFuncName::Equivalence => "↔", // \u{2194}
-                };
-                write!(f, "{}", s)
+                    SecondaryFuncName::Conjunction => String::from(and),
+                    SecondaryFuncName::Disjunction => String::from(or),
+                    SecondaryFuncName::Implicature => String::from(to),
+                    SecondaryFuncName::Equivalence => String::from(iff),
+                }
             },
+            Self::Forall(c)     => format!("{}{}.", forall, c),
+            Self::Exists(c)     => format!("{}{}.", exists, c),
         }
     }
 }
 
+impl Alphabet {
+    // the symbols of s, separated by spaces as typed at the prompt.
+    // a quantifier may also be written as a word, as in "forall P." or "\\exists Q.".
+    pub fn sentence(s: &str) -> Result<Vec<Self>, &'static str> {
+        let mut sentence = vec![];
+        let mut words = s.split_whitespace();
+        while let Some(word) = words.next() {
+            let quantifier =
+                match word {
+                    "forall" | "\\forall" => '∀',
+                    "exists" | "\\exists" => '∃',
+                    x => {
+                        sentence.push(Self::try_from(x)?);
+                        continue;
+                    },
+                };
+            let bound = words.next().ok_or("Not an alphabet")?;
+            sentence.push(Self::try_from(format!("{}{}", quantifier, bound).as_str())?);
+        }
+        Ok(sentence)
+    }
+}
+
 impl std::convert::TryFrom<&str> for Alphabet {
     type Error = &'static str;
 
@@ -79,6 +189,19 @@ impl std::convert::TryFrom<&str> for Alphabet {
             // "|" => Ok( Self::Disjunction ),
             // "->" => Ok( Self::Implicature ),
             // "=" => Ok( Self::Equivalence ),
+            x if
+                x.chars().count() == 3 &&
+                (x.starts_with('∀') || x.starts_with('∃')) &&
+                x.ends_with('.') &&
+                x.chars().nth(1).unwrap().is_uppercase()
+                => {
+                    let c = x.chars().nth(1).unwrap();
+                    if x.starts_with('∀') {
+                        Ok( Self::Forall(c) )
+                    } else {
+                        Ok( Self::Exists(c) )
+                    }
+            },
             x if
                 x.chars().count() == 1 &&
                 x.chars().next().unwrap().is_uppercase()
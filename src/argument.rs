@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use crate::alphabet::{Alphabet, SymbolSet};
+use crate::formula::{EvalFormula, Formula, SecondaryFuncName, TableOptions, Var};
+use crate::table::RowOrder;
+
+// premises and a conclusion, valid if every interpretation making all the premises true
+// also makes the conclusion true
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Argument {
+    pub premises: Vec<Formula>,
+    pub conclusion: Formula,
+}
+
+impl Argument {
+    // premises separated by commas, then "|-" and the conclusion, such as "P -> Q , P |- Q".
+    // the symbols of each formula are separated by spaces as in Formula::parse.
+    pub fn parse(s: &str) -> Result<Self, ()> {
+        let (premises, conclusion) = s.split_once("|-").ok_or(())?;
+        let premises =
+            if premises.trim().is_empty() {
+                vec![]
+            } else {
+                premises.split(',').map(parse_formula).collect::<Result<_, _>>()?
+            };
+        Ok(Self { premises, conclusion: parse_formula(conclusion)? })
+    }
+
+    // the premises followed by the conclusion
+    pub fn formulas(&self) -> Vec<Formula> {
+        let mut formulas = self.premises.clone();
+        formulas.push(self.conclusion.clone());
+        formulas
+    }
+
+    // the first interpretation, from all false to all true, making the premises true and the conclusion false.
+    // it is the first model of the premises and the negated conclusion, found without building the table.
+    pub fn counterexample(&self) -> Option<HashMap<Var, bool>> {
+        let negated = Formula::Negation(Box::new(self.conclusion.clone()));
+        let formula = self.premises.iter().rev().fold(negated, |rhs, premise| Formula::SecondaryFunc {
+            name: SecondaryFuncName::Conjunction,
+            lhs: Box::new(premise.clone()),
+            rhs: Box::new(rhs),
+        });
+        let compiled = formula.compile();
+        let letters = compiled.letters();
+        compiled.first_model()
+            .map(|i| letters.iter().copied().zip(RowOrder::FalseFirst.interpretation(i, letters.len())).collect())
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.counterexample().is_none()
+    }
+
+    // "valid", or "invalid" and a counterexample
+    pub fn verdict(&self, options: &TableOptions) -> String {
+        match self.counterexample() {
+            None => String::from("valid"),
+            Some(counterexample) => {
+                let mut letters: Vec<_> = counterexample.into_iter().collect();
+                letters.sort();
+                let assignment: Vec<_> = letters.iter()
+                    .map(|(c, b)| format!("{}={}", c, options.labels.get(*b)))
+                    .collect();
+                format!("invalid, counterexample: {}", assignment.join(", "))
+            },
+        }
+    }
+
+    // the table of the premises and the conclusion, and the verdict.
+    // critical rows, where all the premises are true, are marked with *, or with ! if the conclusion is false there.
+    pub fn to_table(&self, options: &TableOptions) -> String {
+        let critical = |evaluated: &[EvalFormula]| {
+            let (conclusion, premises) = evaluated.split_last().unwrap();
+            match (premises.iter().all(|x| x.is_true()), conclusion.is_true()) {
+                (true, true) => "*",
+                (true, false) => "!",
+                _ => "",
+            }
+        };
+        let mut out = vec![];
+        Formula::write_marked_table(&self.formulas(), &mut out, options, Some(("critical", &critical))).unwrap();
+        let mut s = String::from_utf8(out).unwrap();
+        s.push_str(&self.verdict(options));
+        s.push('\n');
+        s
+    }
+
+    pub fn to_string_with(&self, symbols: SymbolSet) -> String {
+        let premises: Vec<_> = self.premises.iter().map(|premise| premise.to_string_with(symbols)).collect();
+        let mut s = premises.join(", ");
+        if ! s.is_empty() {
+            s.push(' ');
+        }
+        s.push_str(&format!("{} {}", symbols.turnstile(), self.conclusion.to_string_with(symbols)));
+        s
+    }
+}
+
+impl std::fmt::Display for Argument {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_string_with(SymbolSet::Unicode))
+    }
+}
+
+fn parse_formula(s: &str) -> Result<Formula, ()> {
+    let sentence = Alphabet::sentence(s).map_err(|_| ())?;
+    Formula::parse(&sentence)
+}
+
+
+#[cfg(test)]
+mod argument_tests {
+    use super::*;
+
+    #[test]
+    fn parse_argument() {
+        let argument = Argument::parse("P -> Q , P |- Q").unwrap();
+        assert_eq!(argument.premises.len(), 2);
+        assert_eq!(argument.to_string(), "P → Q, P ⊢ Q");
+        assert_eq!(Argument::parse("|- P | ! P").unwrap().to_string(), "⊢ P ∨ ¬ P");
+        assert!(Argument::parse("P -> Q , P").is_err());
+        assert!(Argument::parse("P -> , P |- Q").is_err());
+    }
+
+    #[test]
+    fn validity() {
+        assert!(Argument::parse("P -> Q , P |- Q").unwrap().is_valid());
+        assert!(Argument::parse("P -> Q , ! Q |- ! P").unwrap().is_valid());
+
+        let affirming_the_consequent = Argument::parse("P -> Q , Q |- P").unwrap();
+        assert_eq!(affirming_the_consequent.counterexample(), Some(HashMap::from([('P', false), ('Q', true)])));
+        assert_eq!(affirming_the_consequent.verdict(&TableOptions::default()), "invalid, counterexample: P=F, Q=T");
+    }
+
+    #[test]
+    fn counterexample_without_the_table() {
+        // 2^40 rows, the first of which is a counterexample
+        let letters = ('A'..='Z').chain('a'..='n');
+        let conclusion = letters.map(Formula::Letter)
+            .reduce(|lhs, rhs| Formula::SecondaryFunc { name: SecondaryFuncName::Conjunction, lhs: Box::new(lhs), rhs: Box::new(rhs) })
+            .unwrap();
+        let argument = Argument { premises: vec![], conclusion };
+        let counterexample = argument.counterexample().unwrap();
+        assert_eq!(counterexample.len(), 40);
+        assert!(counterexample.values().all(|b| ! b));
+    }
+
+    #[test]
+    fn critical_rows() {
+        let argument = Argument::parse("P -> Q , Q |- P").unwrap();
+        assert_eq!(
+            argument.to_table(&TableOptions::default()),
+            String::from(
+"P Q | P → Q | Q | P | critical 
+F F | F T F | F | F |          
+F T | F T T | T | F | !        
+T F | T F F | F | T |          
+T T | T T T | T | T | *        
+invalid, counterexample: P=F, Q=T
+"
+            )
+        );
+    }
+}
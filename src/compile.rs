@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::formula::{Formula, SecondaryFuncName, Var};
+use crate::parallel;
+
+// fewer blocks than this per thread are not worth starting a thread for
+const BLOCKS_PER_THREAD: u128 = 1 << 8;
+
+// the values of a letter in the 64 rows of a block, for the 6 letters changing within a block.
+// bit b of a block is row 64 * block + b, counting from all false with the first letter most significant.
+const PATTERNS: [u64; 6] = [
+    0xAAAA_AAAA_AAAA_AAAA,
+    0xCCCC_CCCC_CCCC_CCCC,
+    0xF0F0_F0F0_F0F0_F0F0,
+    0xFF00_FF00_FF00_FF00,
+    0xFFFF_0000_FFFF_0000,
+    0xFFFF_FFFF_0000_0000,
+];
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+enum Instruction {
+    Letter(usize), // push the values of the i-th letter
+    True,
+    False,
+    Not,
+    SecondaryFunc(SecondaryFuncName), // pop the rhs and the lhs, and push the result
+}
+
+// a formula compiled to a postfix program over bitmasks, evaluating 64 rows per instruction.
+// quantifiers are expanded at compile time, so the program only has propositional connectives.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Compiled {
+    letters: Vec<Var>,
+    program: Vec<Instruction>,
+    depth: usize, // the largest stack the program needs
+}
+
+impl Compiled {
+    pub fn new(formula: &Formula) -> Self {
+        let letters: Vec<_> = formula.free_letters().into_iter().collect();
+        Self::with_letters(formula, &letters)
+    }
+
+    // rows over the given letters, which have to include the free letters of the formula,
+    // so that formulas over different letters can be evaluated in the same rows
+    pub fn with_letters(formula: &Formula, letters: &[Var]) -> Self {
+        let letters = letters.to_vec();
+        assert!(letters.len() <= 128, "a truth table can have at most 128 letters");
+        let index: HashMap<_, _> = letters.iter().enumerate().map(|(i, c)| (*c, i)).collect();
+
+        let mut program = vec![];
+        compile(formula, &index, &mut program);
+
+        let mut depth = 0;
+        let mut max_depth = 0;
+        for instruction in &program {
+            match instruction {
+                Instruction::Letter(..) | Instruction::True | Instruction::False => depth += 1,
+                Instruction::Not => (),
+                Instruction::SecondaryFunc(..) => depth -= 1,
+            }
+            max_depth = max_depth.max(depth);
+        }
+
+        Self { letters, program, depth: max_depth }
+    }
+
+    // the free letters of the formula, the first one being the most significant bit of a row index
+    pub fn letters(&self) -> &[Var] {
+        &self.letters
+    }
+
+    // the number of blocks of 64 rows, the last one only partly used below 6 letters
+    pub fn block_cnt(&self) -> u128 {
+        1 << self.letters.len().saturating_sub(6)
+    }
+
+    // the rows of a block that exist
+    fn valid(&self) -> u64 {
+        match self.letters.len() {
+            n if n >= 6 => ! 0,
+            n => (1 << (1 << n)) - 1,
+        }
+    }
+
+    fn letter(&self, i: usize, block: u128) -> u64 {
+        match self.letters.len() - i - 1 {
+            bit if bit < 6 => PATTERNS[bit],
+            bit if block >> (bit - 6) & 1 == 1 => ! 0,
+            _ => 0,
+        }
+    }
+
+    // the values of the formula in the 64 rows of a block, as the bits of the result
+    pub fn evaluate_block(&self, block: u128) -> u64 {
+        let mut stack: Vec<u64> = Vec::with_capacity(self.depth);
+        for instruction in &self.program {
+            let value =
+                match instruction {
+                    Instruction::Letter(i) => self.letter(*i, block),
+                    Instruction::True => ! 0,
+                    Instruction::False => 0,
+                    Instruction::Not => ! stack.pop().unwrap(),
+                    Instruction::SecondaryFunc(name) => {
+                        let rhs = stack.pop().unwrap();
+                        let lhs = stack.pop().unwrap();
+                        match name {
+                            SecondaryFuncName::Conjunction => lhs & rhs,
+                            SecondaryFuncName::Disjunction => lhs | rhs,
+                            SecondaryFuncName::Implicature => ! lhs | rhs,
+                            SecondaryFuncName::Equivalence => ! (lhs ^ rhs),
+                        }
+                    },
+                };
+            stack.push(value);
+        }
+        stack.pop().unwrap() & self.valid()
+    }
+
+    // the value of the formula in a row, counting from all false with the first letter most significant
+    pub fn value(&self, index: u128) -> bool {
+        self.evaluate_block(index >> 6) >> (index & 63) & 1 == 1
+    }
+
+    // the first row where the formula is true, counting from all false, without looking at the rows after it
+    pub fn first_model(&self) -> Option<u128> {
+        (0..self.block_cnt())
+            .find_map(|block| {
+                let values = self.evaluate_block(block);
+                (values != 0).then(|| block << 6 | values.trailing_zeros() as u128)
+            })
+    }
+
+    // the checks below use a thread per available core for large formulas; the _with variants take
+    // the number of threads, 0 meaning one per core
+
+    pub fn is_tautology(&self) -> bool {
+        self.is_tautology_with(0)
+    }
+
+    pub fn is_tautology_with(&self, threads: usize) -> bool {
+        let valid = self.valid();
+        self.all_blocks(threads, |values| values == valid)
+    }
+
+    pub fn is_satisfiable(&self) -> bool {
+        self.is_satisfiable_with(0)
+    }
+
+    pub fn is_satisfiable_with(&self, threads: usize) -> bool {
+        ! self.all_blocks(threads, |values| values == 0)
+    }
+
+    // the number of rows where the formula is true
+    pub fn count_models(&self) -> u128 {
+        self.count_models_with(0)
+    }
+
+    pub fn count_models_with(&self, threads: usize) -> u128 {
+        let ranges = parallel::split(0, self.block_cnt() - 1, parallel::threads(threads), BLOCKS_PER_THREAD);
+        parallel::map(&ranges, |first, last| {
+            (first..=last).map(|block| self.evaluate_block(block).count_ones() as u128).sum::<u128>()
+        }).into_iter().sum()
+    }
+
+    // true if every block satisfies the predicate. every thread stops as soon as one finds a block that does not.
+    fn all_blocks(&self, threads: usize, predicate: impl Fn(u64) -> bool + Sync) -> bool {
+        let failed = AtomicBool::new(false);
+        let ranges = parallel::split(0, self.block_cnt() - 1, parallel::threads(threads), BLOCKS_PER_THREAD);
+        parallel::map(&ranges, |first, last| {
+            for block in first..=last {
+                if failed.load(Ordering::Relaxed) {
+                    return;
+                }
+                if ! predicate(self.evaluate_block(block)) {
+                    failed.store(true, Ordering::Relaxed);
+                    return;
+                }
+            }
+        });
+        ! failed.load(Ordering::Relaxed)
+    }
+}
+
+// ∀P. A is compiled as A[P:=⊤] ∧ A[P:=⊥], and ∃P. A as A[P:=⊤] ∨ A[P:=⊥]
+fn compile(f: &Formula, index: &HashMap<Var, usize>, program: &mut Vec<Instruction>) {
+    match f {
+        Formula::Letter(c) => program.push(Instruction::Letter(index[c])),
+        Formula::True => program.push(Instruction::True),
+        Formula::False => program.push(Instruction::False),
+        Formula::Negation(sub) => {
+            compile(sub, index, program);
+            program.push(Instruction::Not);
+        },
+        Formula::SecondaryFunc { name, lhs, rhs } => {
+            compile(lhs, index, program);
+            compile(rhs, index, program);
+            program.push(Instruction::SecondaryFunc(*name));
+        },
+        Formula::Forall(v, body) | Formula::Exists(v, body) => {
+            compile(&body.substitute(&HashMap::from([(*v, Formula::True)])), index, program);
+            compile(&body.substitute(&HashMap::from([(*v, Formula::False)])), index, program);
+            let name = if matches!(f, Formula::Forall(..)) { SecondaryFuncName::Conjunction } else { SecondaryFuncName::Disjunction };
+            program.push(Instruction::SecondaryFunc(name));
+        },
+    }
+}
+
+
+#[cfg(test)]
+mod compile_tests {
+    use super::*;
+    use crate::table::{Interpretations, RowOrder};
+    use crate::test_util::formula;
+
+    #[test]
+    fn agrees_with_evaluate() {
+        for s in ["( P -> Q ) & ( Q = ! R )", "∀P. P | Q", "∃P. P & ( Q -> P )", "t -> f", "( ( A | B ) & ( C | D ) ) & ( ( E | F ) & ( G -> A ) )"] {
+            let f = formula(s);
+            let compiled = Compiled::new(&f);
+            let letters = compiled.letters();
+            let mut models = 0;
+            for (i, values) in Interpretations::new(letters.len(), RowOrder::FalseFirst).enumerate() {
+                let interpretation: HashMap<_, _> = letters.iter().copied().zip(values).collect();
+                let value = f.evaluate(&interpretation).is_true();
+                assert_eq!(compiled.value(i as u128), value, "{} in row {}", s, i);
+                models += value as u128;
+            }
+            assert_eq!(compiled.count_models(), models);
+        }
+    }
+
+    #[test]
+    fn tautologies() {
+        assert!(Compiled::new(&formula("( P -> Q ) = ( ! Q -> ! P )")).is_tautology());
+        assert!(! Compiled::new(&formula("P -> Q")).is_tautology());
+        assert!(! Compiled::new(&formula("P & ! P")).is_satisfiable());
+        assert!(Compiled::new(&formula("∀P. P | ! P")).is_tautology());
+    }
+
+    #[test]
+    fn threads_agree() {
+        // 2^16 rows, true when the first 8 letters match the last 8 pairwise
+        let letters: Vec<_> = ('A'..='P').collect();
+        let f = (1..8).fold(
+            Formula::SecondaryFunc { name: SecondaryFuncName::Equivalence, lhs: Box::new(Formula::Letter('A')), rhs: Box::new(Formula::Letter('I')) },
+            |lhs, i| Formula::SecondaryFunc {
+                name: SecondaryFuncName::Conjunction,
+                lhs: Box::new(lhs),
+                rhs: Box::new(Formula::SecondaryFunc {
+                    name: SecondaryFuncName::Equivalence,
+                    lhs: Box::new(Formula::Letter(letters[i])),
+                    rhs: Box::new(Formula::Letter(letters[i + 8])),
+                }),
+            },
+        );
+        let compiled = Compiled::new(&f);
+        for threads in [1, 3, 8] {
+            assert_eq!(compiled.count_models_with(threads), 256);
+            assert!(compiled.is_satisfiable_with(threads));
+            assert!(! compiled.is_tautology_with(threads));
+        }
+    }
+
+    #[test]
+    fn first_model() {
+        assert_eq!(Compiled::new(&formula("P & ! Q")).first_model(), Some(0b10));
+        assert_eq!(Compiled::new(&formula("P & ! P")).first_model(), None);
+        let all = formula("( ( A & B ) & ( C & D ) ) & ( ( E & F ) & ( G & H ) )");
+        assert_eq!(Compiled::new(&all).first_model(), Some(255));
+    }
+
+    #[test]
+    fn extra_letters() {
+        let compiled = Compiled::with_letters(&formula("Q"), &['P', 'Q']);
+        assert_eq!(compiled.evaluate_block(0), 0b1010);
+    }
+
+    #[test]
+    fn blocks_past_six_letters() {
+        // true only in the last row
+        let f = formula("( ( A & B ) & ( C & D ) ) & ( ( E & F ) & ( G & H ) )");
+        let compiled = Compiled::new(&f);
+        assert_eq!(compiled.block_cnt(), 4);
+        assert_eq!(compiled.evaluate_block(3), 1 << 63);
+        assert_eq!(compiled.count_models(), 1);
+    }
+}
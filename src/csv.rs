@@ -0,0 +1,111 @@
+use crate::alphabet::SymbolSet;
+use crate::table::TruthTable;
+
+pub struct CsvOptions {
+    pub delimiter: char, // ',' for CSV, '\t' for TSV
+    pub numeric: bool,   // write 1/0 instead of T/F
+    pub symbols: SymbolSet,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self { delimiter: ',', numeric: false, symbols: SymbolSet::default() }
+    }
+}
+
+impl CsvOptions {
+    pub fn tsv() -> Self {
+        Self { delimiter: '\t', ..Self::default() }
+    }
+}
+
+impl TruthTable {
+    // a header row with the letters and the column formulas, then one record per row
+    pub fn to_csv(&self, options: &CsvOptions) -> String {
+        let delimiter = options.delimiter.to_string();
+        let value = |b: bool|
+            match (b, options.numeric) {
+                (true, false) => "T",
+                (false, false) => "F",
+                (true, true) => "1",
+                (false, true) => "0",
+            };
+
+        let header: Vec<_> = self.letters.iter()
+            .map(|c| c.to_string())
+            .chain(self.columns.iter().map(|column| column.to_string_with(options.symbols)))
+            .map(|field| quote(&field, options.delimiter))
+            .collect();
+        let mut s = header.join(&delimiter);
+        s.push('\n');
+
+        for row in &self.rows {
+            let record: Vec<_> = row.interpretation.iter()
+                .chain(row.values.iter())
+                .map(|b| value(*b))
+                .collect();
+            s.push_str(&record.join(&delimiter));
+            s.push('\n');
+        }
+        s
+    }
+}
+
+// quote a field holding anything but ASCII letters, digits and punctuation.
+// spreadsheets guess the encoding of unquoted non-ASCII text, and formula headers contain spaces.
+fn quote(field: &str, delimiter: char) -> String {
+    let plain = field.chars().all(|c| c.is_ascii_graphic() && c != '"' && c != delimiter);
+    if plain {
+        field.to_string()
+    } else {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    }
+}
+
+
+#[cfg(test)]
+mod csv_tests {
+    use super::*;
+    use crate::test_util::formula;
+
+    #[test]
+    fn csv_with_main_connective() {
+        let table = TruthTable::new(vec![formula("P & Q")]);
+        assert_eq!(
+            table.to_csv(&CsvOptions::default()),
+            String::from(
+"P,Q,\"P ∧ Q\"
+F,F,F
+F,T,F
+T,F,F
+T,T,T
+"
+            )
+        );
+    }
+
+    #[test]
+    fn tsv_with_subformulas_and_numeric_values() {
+        let table = TruthTable::subformulas(&formula("! P | Q"));
+        let options = CsvOptions { numeric: true, ..CsvOptions::tsv() };
+        assert_eq!(
+            table.to_csv(&options),
+            String::from(
+"P\tQ\t\"¬ P\"\t\"¬ P ∨ Q\"
+0\t0\t1\t1
+0\t1\t1\t1
+1\t0\t0\t0
+1\t1\t0\t1
+"
+            )
+        );
+    }
+
+    #[test]
+    fn quoting() {
+        assert_eq!(quote("P", ','), "P");
+        assert_eq!(quote("a,b", ','), "\"a,b\"");
+        assert_eq!(quote("say \"hi\"", ','), "\"say \"\"hi\"\"\"");
+        assert_eq!(quote("a,b", '\t'), "a,b");
+    }
+}
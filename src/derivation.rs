@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+use unicode_width::UnicodeWidthStr;
+use crate::formula::{Formula, SecondaryFuncName, SymbolSet};
+use crate::simplify::{self, Law};
+
+// the form a derivation rewrites a formula into
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Target {
+    Nnf,        // negation normal form
+    Cnf,        // conjunctive normal form, quantifiers being expanded first
+    Simplified, // the result of Formula::simplify
+}
+
+// one rewrite of a derivation
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Step {
+    pub law: Law,
+    pub position: Vec<usize>, // child indices from the root to the rewritten subformula
+    pub before: Formula,      // the rewritten subformula
+    pub after: Formula,       // the subformula it was rewritten into
+    pub result: Formula,      // the whole formula after the rewrite
+}
+
+impl Step {
+    // a step is sound if the law turned the subformula into an equivalent one
+    pub fn is_sound(&self) -> bool {
+        self.before.is_equivalent(&self.after)
+    }
+}
+
+// a sequence of rewrites turning start into the target form, one law at a time
+pub struct Derivation {
+    pub start: Formula,
+    pub target: Target,
+    pub steps: Vec<Step>,
+}
+
+impl Derivation {
+    // rewrite the outermost, leftmost subformula a law applies to, until none applies.
+    // for CNF the quantifiers are expanded and the formula brought into NNF before distributing.
+    pub fn new(formula: &Formula, target: Target) -> Self {
+        let mut steps = vec![];
+        let mut current = formula.clone();
+        loop {
+            let rewrite =
+                match target {
+                    Target::Nnf => find_rewrite(&current, &mut vec![], &nnf_root),
+                    Target::Cnf => find_rewrite(&current, &mut vec![], &expansion_root)
+                        .or_else(|| find_rewrite(&current, &mut vec![], &nnf_root))
+                        .or_else(|| find_rewrite(&current, &mut vec![], &distribution_root)),
+                    Target::Simplified => find_rewrite(&current, &mut vec![], &simplify::rewrite_root),
+                };
+            let (position, law, after) =
+                match rewrite {
+                    Some(rewrite) => rewrite,
+                    None => break,
+                };
+            let before = current.subformula_at(&position).unwrap().clone();
+            current = current.replace_at(&position, after.clone());
+            steps.push(Step { law, position, before, after, result: current.clone() });
+        }
+        Self { start: formula.clone(), target, steps }
+    }
+
+    // the formula at the end of the derivation
+    pub fn result(&self) -> &Formula {
+        self.steps.last().map_or(&self.start, |step| &step.result)
+    }
+
+    // every step is sound and every line is equivalent to the one before it
+    pub fn is_sound(&self) -> bool {
+        let mut previous = &self.start;
+        for step in &self.steps {
+            if ! step.is_sound() || ! step.result.is_equivalent(previous) {
+                return false;
+            }
+            previous = &step.result;
+        }
+        true
+    }
+}
+
+impl std::fmt::Display for Derivation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_string_with(SymbolSet::Unicode))
+    }
+}
+
+impl Derivation {
+    // one numbered line per formula, with the law that produced it
+    pub fn to_string_with(&self, symbols: SymbolSet) -> String {
+        let lines: Vec<_> = std::iter::once(&self.start)
+            .chain(self.steps.iter().map(|step| &step.result))
+            .map(|formula| formula.to_string_with(symbols))
+            .collect();
+        let width = lines.iter().map(|line| line.width()).max().unwrap_or(0);
+
+        let mut s = String::new();
+        for (i, line) in lines.iter().enumerate() {
+            s.push_str(&format!("{:>3}. {}", i, line));
+            if i > 0 {
+                let step = &self.steps[i - 1];
+                let pad = width - line.width();
+                s.push_str(&format!(
+                    "{}    {} at {}: {} {} {}",
+                    " ".repeat(pad),
+                    step.law,
+                    position_to_string(&step.position),
+                    step.before.to_string_with(symbols),
+                    symbols.arrow(),
+                    step.after.to_string_with(symbols)
+                ));
+            }
+            s.push('\n');
+        }
+        s
+    }
+}
+
+// positions are written as 1-based child numbers separated by dots
+fn position_to_string(position: &[usize]) -> String {
+    if position.is_empty() {
+        return String::from("root");
+    }
+    position.iter().map(|i| (i + 1).to_string()).collect::<Vec<_>>().join(".")
+}
+
+// search in pre-order for the first subformula rewrite_root applies to
+fn find_rewrite(
+    f: &Formula,
+    position: &mut Vec<usize>,
+    rewrite_root: &dyn Fn(&Formula) -> Option<(Law, Formula)>
+) -> Option<(Vec<usize>, Law, Formula)> {
+    if let Some((law, after)) = rewrite_root(f) {
+        return Some((position.clone(), law, after));
+    }
+    for (i, child) in f.children().into_iter().enumerate() {
+        position.push(i);
+        let found = find_rewrite(child, position, rewrite_root);
+        position.pop();
+        if found.is_some() {
+            return found;
+        }
+    }
+    None
+}
+
+fn negation(f: &Formula) -> Formula {
+    Formula::Negation(Box::new(f.clone()))
+}
+
+fn secondary_func(name: SecondaryFuncName, lhs: Formula, rhs: Formula) -> Formula {
+    Formula::SecondaryFunc { name, lhs: Box::new(lhs), rhs: Box::new(rhs) }
+}
+
+// the laws moving negations inwards and eliminating → and ↔
+fn nnf_root(f: &Formula) -> Option<(Law, Formula)> {
+    match f {
+        Formula::SecondaryFunc { name: SecondaryFuncName::Implicature, lhs, rhs } => {
+            Some((Law::ImplicationElimination, secondary_func(SecondaryFuncName::Disjunction, negation(lhs), (**rhs).clone())))
+        },
+        Formula::SecondaryFunc { name: SecondaryFuncName::Equivalence, lhs, rhs } => {
+            Some((Law::EquivalenceElimination, secondary_func(
+                SecondaryFuncName::Conjunction,
+                secondary_func(SecondaryFuncName::Implicature, (**lhs).clone(), (**rhs).clone()),
+                secondary_func(SecondaryFuncName::Implicature, (**rhs).clone(), (**lhs).clone())
+            )))
+        },
+        Formula::Negation(sub) => {
+            match sub.as_ref() {
+                Formula::Negation(x) => Some((Law::DoubleNegation, (**x).clone())),
+                Formula::True => Some((Law::Complement, Formula::False)),
+                Formula::False => Some((Law::Complement, Formula::True)),
+                Formula::SecondaryFunc { name: SecondaryFuncName::Conjunction, lhs, rhs } => {
+                    Some((Law::DeMorgan, secondary_func(SecondaryFuncName::Disjunction, negation(lhs), negation(rhs))))
+                },
+                Formula::SecondaryFunc { name: SecondaryFuncName::Disjunction, lhs, rhs } => {
+                    Some((Law::DeMorgan, secondary_func(SecondaryFuncName::Conjunction, negation(lhs), negation(rhs))))
+                },
+                Formula::Forall(v, body) => Some((Law::QuantifierNegation, Formula::Exists(*v, Box::new(negation(body))))),
+                Formula::Exists(v, body) => Some((Law::QuantifierNegation, Formula::Forall(*v, Box::new(negation(body))))),
+                _ => None,
+            }
+        },
+        _ => None,
+    }
+}
+
+// replace a quantifier by the conjunction or disjunction of its two instances, as Compiled does
+fn expansion_root(f: &Formula) -> Option<(Law, Formula)> {
+    let (name, v, body) =
+        match f {
+            Formula::Forall(v, body) => (SecondaryFuncName::Conjunction, v, body),
+            Formula::Exists(v, body) => (SecondaryFuncName::Disjunction, v, body),
+            _ => return None,
+        };
+    Some((Law::QuantifierExpansion, secondary_func(
+        name,
+        body.substitute(&HashMap::from([(*v, Formula::True)])),
+        body.substitute(&HashMap::from([(*v, Formula::False)]))
+    )))
+}
+
+// distribute ∨ over ∧ on either side
+fn distribution_root(f: &Formula) -> Option<(Law, Formula)> {
+    if let Formula::SecondaryFunc { name: SecondaryFuncName::Disjunction, lhs, rhs } = f {
+        if let Formula::SecondaryFunc { name: SecondaryFuncName::Conjunction, lhs: q, rhs: r } = rhs.as_ref() {
+            return Some((Law::Distribution, secondary_func(
+                SecondaryFuncName::Conjunction,
+                secondary_func(SecondaryFuncName::Disjunction, (**lhs).clone(), (**q).clone()),
+                secondary_func(SecondaryFuncName::Disjunction, (**lhs).clone(), (**r).clone())
+            )));
+        }
+        if let Formula::SecondaryFunc { name: SecondaryFuncName::Conjunction, lhs: p, rhs: q } = lhs.as_ref() {
+            return Some((Law::Distribution, secondary_func(
+                SecondaryFuncName::Conjunction,
+                secondary_func(SecondaryFuncName::Disjunction, (**p).clone(), (**rhs).clone()),
+                secondary_func(SecondaryFuncName::Disjunction, (**q).clone(), (**rhs).clone())
+            )));
+        }
+    }
+    None
+}
+
+
+#[cfg(test)]
+mod derivation_tests {
+    use super::*;
+    use crate::test_util::formula;
+
+    #[test]
+    fn nnf_steps() {
+        let derivation = Derivation::new(&formula("! ( P -> Q )"), Target::Nnf);
+        let steps: Vec<_> = derivation.steps.iter().map(|step| (step.law, step.position.clone())).collect();
+        assert_eq!(
+            steps,
+            vec![
+                (Law::ImplicationElimination, vec![0]),
+                (Law::DeMorgan, vec![]),
+                (Law::DoubleNegation, vec![0]),
+            ]
+        );
+        assert_eq!(*derivation.result(), formula("P & ! Q"));
+    }
+
+    #[test]
+    fn cnf_distributes_after_nnf() {
+        let derivation = Derivation::new(&formula("P | ( Q & ! ! R )"), Target::Cnf);
+        assert_eq!(derivation.steps[0].law, Law::DoubleNegation);
+        assert_eq!(*derivation.result(), formula("( P | Q ) & ( P | R )"));
+    }
+
+    #[test]
+    fn cnf_expands_quantifiers() {
+        let derivation = Derivation::new(&formula("∀P. P | ( Q & R )"), Target::Cnf);
+        assert_eq!(derivation.steps[0].law, Law::QuantifierExpansion);
+        assert_eq!(*derivation.result(), formula("( ( t | Q ) & ( t | R ) ) & ( ( f | Q ) & ( f | R ) )"));
+        assert!(derivation.is_sound());
+    }
+
+    #[test]
+    fn simplified_matches_simplify() {
+        let f = formula("( ( P & t ) | f ) & ! ! P");
+        let derivation = Derivation::new(&f, Target::Simplified);
+        assert_eq!(*derivation.result(), f.simplify());
+    }
+
+    #[test]
+    fn every_step_is_sound() {
+        let inputs = [
+            "( P = Q ) -> ! ( R | ( P & Q ) )",
+            "! ( ∀P. P | Q ) & ( Q -> f )",
+            "( ( P & Q ) | ( R & ! P ) ) = Q",
+        ];
+        for input in inputs {
+            for target in [Target::Nnf, Target::Cnf, Target::Simplified] {
+                let derivation = Derivation::new(&formula(input), target);
+                assert!(derivation.is_sound(), "{}", derivation);
+            }
+        }
+    }
+
+    #[test]
+    fn fmt_derivation() {
+        let derivation = Derivation::new(&formula("! ! P -> Q"), Target::Nnf);
+        assert_eq!(
+            derivation.to_string(),
+            String::from(
+"  0. ¬ ¬ P → Q
+  1. ¬ ¬ ¬ P ∨ Q    implication elimination at root: ¬ ¬ P → Q ⟹ ¬ ¬ ¬ P ∨ Q
+  2. ¬ P ∨ Q        double negation at 1: ¬ ¬ ¬ P ⟹ ¬ P
+"
+            )
+        );
+        assert!(derivation.to_string_with(SymbolSet::Ascii).is_ascii());
+        assert!(derivation.to_string_with(SymbolSet::Latex).contains(": \\neg \\neg P \\to Q \\Longrightarrow \\neg \\neg \\neg P \\lor Q"));
+    }
+}
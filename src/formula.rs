@@ -1,7 +1,17 @@
-use std::collections::{HashMap, BTreeSet};
-pub use crate::alphabet::{Alphabet, SecondaryFuncName};
+use std::collections::{BTreeMap, HashMap, BTreeSet};
+use std::io::Write;
+use unicode_width::UnicodeWidthStr;
+pub use crate::alphabet::{Alphabet, SecondaryFuncName, SymbolSet};
+pub use crate::table::{Interpretations, Labels, RowFilter, RowOrder, Rows};
+pub use crate::visit::{Fold, Visitor};
+use crate::compile::Compiled;
+use crate::table::BlockCache;
+use crate::parallel;
 
-#[derive(PartialEq, Eq, Debug)]
+// a propositional letter
+pub type Var = char;
+
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
 pub enum Formula {
     Letter(char),
     True,
@@ -12,97 +22,610 @@ pub enum Formula {
         lhs: Box<Formula>,
         rhs: Box<Formula>,
     },
+    Forall(Var, Box<Formula>),
+    Exists(Var, Box<Formula>),
 }
 
 impl std::fmt::Display for Formula {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let sentence = self.to_sentence();
-        let mut s = String::new();
-        for x in sentence {
-            s.push_str(&format!("{:2}", x.to_string()));
+        write!(f, "{}", self.to_string_with(SymbolSet::Unicode))
+    }
+}
+
+// the truth values of letters, for Formula::eval
+pub trait Assignment {
+    fn get(&self, v: Var) -> Option<bool>;
+}
+
+impl Assignment for HashMap<Var, bool> {
+    fn get(&self, v: Var) -> Option<bool> {
+        HashMap::get(self, &v).copied()
+    }
+}
+
+impl Assignment for BTreeMap<Var, bool> {
+    fn get(&self, v: Var) -> Option<bool> {
+        BTreeMap::get(self, &v).copied()
+    }
+}
+
+impl Assignment for [(Var, bool)] {
+    fn get(&self, v: Var) -> Option<bool> {
+        self.iter().find(|(c, _)| *c == v).map(|(_, b)| *b)
+    }
+}
+
+impl<const N: usize> Assignment for [(Var, bool); N] {
+    fn get(&self, v: Var) -> Option<bool> {
+        Assignment::get(&self[..], v)
+    }
+}
+
+// a free letter the assignment has no truth value for
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct UnboundVariable(pub Var);
+
+impl std::fmt::Display for UnboundVariable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "no truth value for {}", self.0)
+    }
+}
+
+impl std::error::Error for UnboundVariable {}
+
+// the letters bound by the quantifiers around a subformula, innermost first, kept on the call stack
+pub(crate) struct Bound<'a> {
+    pub(crate) var: Var,
+    pub(crate) value: bool,
+    pub(crate) outer: Option<&'a Bound<'a>>,
+}
+
+impl Bound<'_> {
+    pub(crate) fn get(bound: Option<&Self>, v: Var) -> Option<bool> {
+        let mut bound = bound;
+        while let Some(b) = bound {
+            if b.var == v {
+                return Some(b.value);
+            }
+            bound = b.outer;
         }
-        write!(f, "{}", s)
+        None
     }
 }
 
+// options for Formula::truth_value_table_with and Formula::truth_value_table_of
+#[derive(Default, Clone)]
+pub struct TableOptions {
+    pub color: bool, // ANSI colors: T in green, F in red, the main connective column in bold
+    pub symbols: SymbolSet,
+    pub labels: Labels,
+    pub order: RowOrder,
+    pub filter: RowFilter,
+    pub threads: usize, // threads rendering rows of large tables, 0 meaning one per available core
+}
+
+// the mark of a row in the last column of Formula::write_marked_table, given the evaluated formulas
+pub(crate) type Mark<'a> = &'a (dyn Fn(&[EvalFormula]) -> &'static str + Sync);
+
+// the rows a thread renders at a time, keeping what is held in memory bounded for any table size
+const ROWS_PER_CHUNK: u128 = 1 << 12;
+
 impl Formula {
     pub fn truth_value_table(&self) -> String {
+        self.truth_value_table_with(&TableOptions::default())
+    }
+
+    pub fn truth_value_table_with(&self, options: &TableOptions) -> String {
+        Self::truth_value_table_of(std::slice::from_ref(self), options)
+    }
+
+    // the same table, written row by row so that it never has to fit in memory
+    pub fn write_truth_value_table(&self, out: &mut dyn Write, options: &TableOptions) -> std::io::Result<()> {
+        Self::write_truth_value_table_of(std::slice::from_ref(self), out, options)
+    }
+
+    // the rows of the truth table, evaluated one at a time
+    pub fn rows(&self) -> Rows {
+        self.rows_with(RowOrder::default())
+    }
+
+    pub fn rows_with(&self, order: RowOrder) -> Rows {
+        Rows::new(self, order)
+    }
+
+    // one table over the letters of all the formulas, with a block of columns per formula.
+    // with more than one formula, a last column marks the rows where their truth values differ,
+    // and a filter on the truth value looks at the last formula.
+    pub fn truth_value_table_of(formulas: &[Formula], options: &TableOptions) -> String {
+        let mut out = vec![];
+        Self::write_truth_value_table_of(formulas, &mut out, options).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    pub fn write_truth_value_table_of(formulas: &[Formula], out: &mut dyn Write, options: &TableOptions) -> std::io::Result<()> {
+        let differ = |evaluated: &[EvalFormula]| {
+            if evaluated.iter().any(|x| x.is_true() != evaluated[0].is_true()) { "*" } else { "" }
+        };
+        let marker = (formulas.len() > 1).then_some((options.symbols.not_equal(), &differ as Mark));
+        Self::write_marked_table(formulas, out, options, marker)
+    }
+
+    // a table of several formulas, optionally followed by a column with a header
+    // and a mark computed from the evaluated formulas of each row
+    pub(crate) fn write_marked_table(
+        formulas: &[Formula],
+        out: &mut dyn Write,
+        options: &TableOptions,
+        marker: Option<(&str, Mark)>,
+    ) -> std::io::Result<()> {
         let mut letters = BTreeSet::new();
-        for x in self.to_sentence() {
-            if let Alphabet::Letter(c) = x {
-                letters.insert(c);
-            }
+        for formula in formulas {
+            letters.append(&mut formula.free_letters());
         }
         let letters: Vec<_> = letters.into_iter().collect();
 
+        let letter_widths: Vec<_> = letters.iter().map(|c| column_width(&c.to_string(), &options.labels)).collect();
+
         let mut header = String::new();
-        for c in &letters {
-            header.push(*c);
+        for (c, width) in letters.iter().zip(&letter_widths) {
+            header.push_str(&cell(&c.to_string(), *width, None, false, false));
+        }
+        for formula in formulas {
+            header.push('|');
+            header.push(' ');
+            header.push_str(&formula.to_string_padded(options));
+        }
+        if let Some((marker, _)) = marker {
+            header.push('|');
             header.push(' ');
+            header.push_str(&cell(marker, marker.width(), None, false, false));
         }
-        header.push('|');
-        header.push(' ');
-        header.push_str(&format!("{}", self));
 
-        let mut table = header;
-        table.push('\n');
+        writeln!(out, "{}", header)?;
 
-        let letter_cnt = letters.len();
-        for i in 0..1<<letter_cnt {
-            let mut interpretation = HashMap::new();
-            for j in 0..letter_cnt {
-                if i & 1<<(letter_cnt - j - 1) != 0 {
-                    interpretation.insert(letters[j], true);
-                } else {
-                    interpretation.insert(letters[j], false);
+        // letters fixed by the filter are not enumerated, so only the matching rows are evaluated
+        let assignment = &options.filter.assignment;
+        let unassigned: Vec<_> = letters.iter().copied().filter(|c| ! assignment.contains_key(c)).collect();
+        let fixed: HashMap<_, _> = letters.iter()
+            .filter_map(|c| assignment.get(c).map(|b| (*c, *b)))
+            .collect();
+        // rows with the wrong truth value are skipped before building their EvalFormula
+        let compiled_last = formulas.last()
+            .filter(|_| options.filter.value.is_some())
+            .map(|f| Compiled::with_letters(&f.assign(assignment), &unassigned));
+
+        // the rows from first to last_row, in order
+        let rows = |first: u128, last_row: u128| -> String {
+            let mut interpretation = fixed.clone();
+            let mut last = compiled_last.clone().map(BlockCache::new);
+            let mut interpretations = Interpretations::between(unassigned.len(), options.order, first, last_row);
+            let mut s = String::new();
+            while let Some(index) = interpretations.next_index() {
+                let values = interpretations.next().unwrap();
+                if last.as_mut().is_some_and(|last| Some(last.value(index)) != options.filter.value) {
+                    continue;
+                }
+                interpretation.extend(unassigned.iter().copied().zip(values));
+                let evaluated: Vec<_> = formulas.iter().map(|formula| formula.evaluate(&interpretation)).collect();
+                let value = evaluated.last().is_none_or(|x| x.is_true());
+                if ! options.filter.keeps(&interpretation, value) {
+                    continue;
+                }
+                let mut row = String::new();
+                for (c, width) in letters.iter().zip(&letter_widths) {
+                    let b = interpretation.get(c).copied();
+                    row.push_str(&cell(label(b, &options.labels), *width, b, false, options.color));
                 }
+                for x in &evaluated {
+                    row.push('|');
+                    row.push(' ');
+                    row.push_str(&x.tf_string(options));
+                }
+                if let Some((marker, mark)) = marker {
+                    row.push('|');
+                    row.push(' ');
+                    row.push_str(&cell(mark(&evaluated), marker.width(), None, false, false));
+                }
+                s.push_str(&row);
+                s.push('\n');
             }
-            let mut row = String::new();
-            for c in &letters {
-                let tf =
-                    if *interpretation.get(c).unwrap() {
-                        'T'
-                    } else {
-                        'F'
-                    };
-                row.push(tf);
-                row.push(' ');
+            s
+        };
+
+        // rows are rendered in chunks on threads started once, and written in order
+        let end = Interpretations::last_row(unassigned.len());
+        parallel::for_each_chunk(0, end, ROWS_PER_CHUNK, parallel::threads(options.threads), rows, |s| out.write_all(s.as_bytes()))
+    }
+
+    // symbols separated by spaces, without spaces inside brackets
+    pub fn to_string_with(&self, symbols: SymbolSet) -> String {
+        let sentence = self.to_sentence();
+        let mut s = String::new();
+        for (i, x) in sentence.iter().enumerate() {
+            if i > 0 && sentence[i - 1] != Alphabet::OpenBracket && *x != Alphabet::CloseBracket {
+                s.push(' ');
             }
-            row.push('|');
-            row.push(' ');
-            row.push_str(&self.evaluate(&interpretation).to_string_tf());
-            table.push_str(&row);
-            table.push('\n');
+            s.push_str(&x.symbol(symbols));
         }
-        table
+        s
     }
 
-    pub fn evaluate(&self, interpretation: &HashMap<char, bool>) -> EvalFormula {
+    // output every symbol padded to the same width as EvalFormula::to_string_tf,
+    // so that each truth value sits under its symbol
+    pub(crate) fn to_string_padded(&self, options: &TableOptions) -> String {
+        let main = self.main_connective_index();
+        let mut s = String::new();
+        for (i, x) in self.to_sentence().iter().enumerate() {
+            let x = x.symbol(options.symbols);
+            s.push_str(&cell(&x, column_width(&x, &options.labels), None, i == main, options.color));
+        }
+        s
+    }
+
+    // the letters not bound by a quantifier
+    pub fn free_letters(&self) -> BTreeSet<Var> {
+        match self {
+            Self::Letter(c) => BTreeSet::from([*c]),
+            Self::True | Self::False => BTreeSet::new(),
+            Self::Negation(sub) => sub.free_letters(),
+            Self::SecondaryFunc { lhs, rhs, .. } => {
+                let mut letters = lhs.free_letters();
+                letters.append(&mut rhs.free_letters());
+                letters
+            },
+            Self::Forall(v, body) | Self::Exists(v, body) => {
+                let mut letters = body.free_letters();
+                letters.remove(v);
+                letters
+            },
+        }
+    }
+
+    // the direct subformulas, in the order they are written
+    pub fn children(&self) -> Vec<&Formula> {
+        match self {
+            Self::Letter(..) | Self::True | Self::False => vec![],
+            Self::Negation(sub) => vec![sub],
+            Self::SecondaryFunc { lhs, rhs, .. } => vec![lhs, rhs],
+            Self::Forall(_, body) | Self::Exists(_, body) => vec![body],
+        }
+    }
+
+    // the subformula reached by following the child indices in position from the root
+    pub fn subformula_at(&self, position: &[usize]) -> Option<&Formula> {
+        match position.split_first() {
+            None => Some(self),
+            Some((i, rest)) => self.children().get(*i)?.subformula_at(rest),
+        }
+    }
+
+    // a copy of self with the subformula at position replaced by new
+    pub fn replace_at(&self, position: &[usize], new: Formula) -> Formula {
+        let (i, rest) =
+            match position.split_first() {
+                None => return new,
+                Some((i, rest)) => (*i, rest),
+            };
         match self {
-            Self::Letter(c) => EvalFormula::Letter(*c, *interpretation.get(c).unwrap()),
+            Self::Letter(..) | Self::True | Self::False => self.clone(),
+            Self::Negation(sub) => Self::Negation(Box::new(sub.replace_at(rest, new))),
+            Self::SecondaryFunc { name, lhs, rhs } => {
+                if i == 0 {
+                    Self::SecondaryFunc { name: *name, lhs: Box::new(lhs.replace_at(rest, new)), rhs: rhs.clone() }
+                } else {
+                    Self::SecondaryFunc { name: *name, lhs: lhs.clone(), rhs: Box::new(rhs.replace_at(rest, new)) }
+                }
+            },
+            Self::Forall(v, body) => Self::Forall(*v, Box::new(body.replace_at(rest, new))),
+            Self::Exists(v, body) => Self::Exists(*v, Box::new(body.replace_at(rest, new))),
+        }
+    }
+
+    // true if both formulas have the same truth value under every interpretation of their free letters
+    pub fn is_equivalent(&self, other: &Formula) -> bool {
+        Formula::SecondaryFunc {
+            name: SecondaryFuncName::Equivalence,
+            lhs: Box::new(self.clone()),
+            rhs: Box::new(other.clone()),
+        }.is_tautology()
+    }
+
+    // compiled to evaluate 64 rows at a time, see Compiled
+    pub fn compile(&self) -> Compiled {
+        Compiled::new(self)
+    }
+
+    // true under every interpretation of the free letters
+    pub fn is_tautology(&self) -> bool {
+        self.compile().is_tautology()
+    }
+
+    pub fn is_satisfiable(&self) -> bool {
+        self.compile().is_satisfiable()
+    }
+
+    // the number of interpretations of the free letters making the formula true
+    pub fn count_models(&self) -> u128 {
+        self.compile().count_models()
+    }
+
+    // the truth value of the formula, without building an EvalFormula or allocating.
+    // every free letter needs a truth value, even one that would not decide the result.
+    pub fn eval(&self, assignment: &(impl Assignment + ?Sized)) -> Result<bool, UnboundVariable> {
+        self.eval_bound(assignment, None)
+    }
+
+    fn eval_bound(&self, assignment: &(impl Assignment + ?Sized), bound: Option<&Bound>) -> Result<bool, UnboundVariable> {
+        match self {
+            Self::Letter(c) => Bound::get(bound, *c).or_else(|| assignment.get(*c)).ok_or(UnboundVariable(*c)),
+            Self::True => Ok(true),
+            Self::False => Ok(false),
+            Self::Negation(f) => Ok(! f.eval_bound(assignment, bound)?),
+            Self::SecondaryFunc { name, lhs, rhs } => {
+                let lhs = lhs.eval_bound(assignment, bound)?;
+                let rhs = rhs.eval_bound(assignment, bound)?;
+                Ok(match name {
+                    SecondaryFuncName::Conjunction => lhs && rhs,
+                    SecondaryFuncName::Disjunction => lhs || rhs,
+                    SecondaryFuncName::Implicature => ! lhs || rhs,
+                    SecondaryFuncName::Equivalence => lhs == rhs,
+                })
+            },
+            Self::Forall(v, body) | Self::Exists(v, body) => {
+                let if_true = body.eval_bound(assignment, Some(&Bound { var: *v, value: true, outer: bound }))?;
+                let if_false = body.eval_bound(assignment, Some(&Bound { var: *v, value: false, outer: bound }))?;
+                Ok(if matches!(self, Self::Forall(..)) { if_true && if_false } else { if_true || if_false })
+            },
+        }
+    }
+
+    // panics if a free letter has no truth value, see Formula::try_evaluate
+    pub fn evaluate(&self, interpretation: &HashMap<char, bool>) -> EvalFormula {
+        self.try_evaluate(interpretation).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    // every subformula with its truth value, or an error naming a free letter without one
+    pub fn try_evaluate(&self, assignment: &(impl Assignment + ?Sized)) -> Result<EvalFormula, UnboundVariable> {
+        self.evaluate_bound(assignment, None)
+    }
+
+    fn evaluate_bound(&self, assignment: &(impl Assignment + ?Sized), bound: Option<&Bound>) -> Result<EvalFormula, UnboundVariable> {
+        Ok(match self {
+            Self::Letter(c) => {
+                let value = Bound::get(bound, *c).or_else(|| assignment.get(*c)).ok_or(UnboundVariable(*c))?;
+                EvalFormula::Letter(*c, value)
+            },
             Self::True => EvalFormula::True,
             Self::False => EvalFormula::False,
             Self::Negation(f) => {
-                let f = f.evaluate(interpretation);
+                let f = f.evaluate_bound(assignment, bound)?;
                 let truth_value = ! f.is_true();
                 EvalFormula::Negation(Box::new(f), truth_value)
             },
             Self::SecondaryFunc { name, lhs, rhs } => {
-                let lhs = lhs.evaluate(interpretation);
-                let rhs = rhs.evaluate(interpretation);
+                let lhs = lhs.evaluate_bound(assignment, bound)?;
+                let rhs = rhs.evaluate_bound(assignment, bound)?;
                 let is_true = match name {
                     SecondaryFuncName::Conjunction => lhs.is_true() && rhs.is_true(),
                     SecondaryFuncName::Disjunction => lhs.is_true() || rhs.is_true(),
-                    SecondaryFuncName::Implicature => ! (lhs.is_true() && ! rhs.is_true()),
+                    SecondaryFuncName::Implicature => ! lhs.is_true() || rhs.is_true(),
                     SecondaryFuncName::Equivalence => lhs.is_true() == rhs.is_true()
                 };
                 EvalFormula::SecondaryFunc { name: *name, lhs: Box::new(lhs), rhs: Box::new(rhs), truth_value: is_true }
-            }
+            },
+            Self::Forall(v, body) => {
+                let if_true = body.evaluate_bound(assignment, Some(&Bound { var: *v, value: true, outer: bound }))?;
+                let if_false = body.evaluate_bound(assignment, Some(&Bound { var: *v, value: false, outer: bound }))?;
+                let truth_value = if_true.is_true() && if_false.is_true();
+                // keep the instance which decides the truth value: a counterexample if there is one
+                let body = if if_true.is_true() { if_false } else { if_true };
+                EvalFormula::Forall(*v, Box::new(body), truth_value)
+            },
+            Self::Exists(v, body) => {
+                let if_true = body.evaluate_bound(assignment, Some(&Bound { var: *v, value: true, outer: bound }))?;
+                let if_false = body.evaluate_bound(assignment, Some(&Bound { var: *v, value: false, outer: bound }))?;
+                let truth_value = if_true.is_true() || if_false.is_true();
+                // keep the instance which decides the truth value: a witness if there is one
+                let body = if ! if_true.is_true() && if_false.is_true() { if_false } else { if_true };
+                EvalFormula::Exists(*v, Box::new(body), truth_value)
+            },
+        })
+    }
+
+    // a prenex formula is a prefix of quantifiers followed by a quantifier-free matrix
+    pub fn is_prenex(&self) -> bool {
+        match self {
+            Self::Forall(_, body) | Self::Exists(_, body) => body.is_prenex(),
+            _ => self.is_quantifier_free(),
+        }
+    }
+
+    fn is_quantifier_free(&self) -> bool {
+        match self {
+            Self::Letter(..) | Self::True | Self::False => true,
+            Self::Negation(sub) => sub.is_quantifier_free(),
+            Self::SecondaryFunc { lhs, rhs, .. } => lhs.is_quantifier_free() && rhs.is_quantifier_free(),
+            Self::Forall(..) | Self::Exists(..) => false,
+        }
+    }
+
+    // decide a closed prenex QBF.
+    // the prefix is searched depth-first, stopping as soon as a branch decides a quantifier.
+    pub fn solve_qbf(&self) -> Result<bool, &'static str> {
+        if ! self.is_prenex() {
+            return Err("Not a prenex formula");
+        }
+        if ! self.free_letters().is_empty() {
+            return Err("Free letters in formula");
+        }
+        Ok(self.solve_prenex(&mut HashMap::new()))
+    }
+
+    fn solve_prenex(&self, interpretation: &mut HashMap<char, bool>) -> bool {
+        match self {
+            Self::Forall(v, body) | Self::Exists(v, body) => {
+                let is_forall = matches!(self, Self::Forall(..));
+                let shadowed = interpretation.get(v).copied();
+                let mut result = is_forall;
+                for b in [true, false] {
+                    interpretation.insert(*v, b);
+                    if body.solve_prenex(interpretation) != is_forall {
+                        result = ! is_forall;
+                        break;
+                    }
+                }
+                match shadowed {
+                    Some(b) => interpretation.insert(*v, b),
+                    None => interpretation.remove(v),
+                };
+                result
+            },
+            _ => self.evaluate(interpretation).is_true(),
+        }
+    }
+
+    // replace letters by formulas simultaneously.
+    // bound letters are renamed where a replacement would otherwise be captured.
+    pub fn substitute(&self, map: &HashMap<Var, Formula>) -> Formula {
+        match self {
+            Self::Letter(c) => map.get(c).cloned().unwrap_or(Self::Letter(*c)),
+            Self::True => Self::True,
+            Self::False => Self::False,
+            Self::Negation(sub) => Self::Negation(Box::new(sub.substitute(map))),
+            Self::SecondaryFunc { name, lhs, rhs } => Self::SecondaryFunc {
+                name: *name,
+                lhs: Box::new(lhs.substitute(map)),
+                rhs: Box::new(rhs.substitute(map)),
+            },
+            Self::Forall(v, body) | Self::Exists(v, body) => {
+                let mut map = map.clone();
+                map.remove(v);
+                let body_letters = body.free_letters();
+                let captured = map.iter().any(|(x, f)| body_letters.contains(x) && f.free_letters().contains(v));
+                let v =
+                    if captured {
+                        let mut used = body_letters;
+                        for f in map.values() {
+                            used.append(&mut f.free_letters());
+                        }
+                        let fresh = Self::fresh_letter(&used);
+                        map.insert(*v, Self::Letter(fresh));
+                        fresh
+                    } else {
+                        *v
+                    };
+                let body = Box::new(body.substitute(&map));
+                if let Self::Forall(..) = self {
+                    Self::Forall(v, body)
+                } else {
+                    Self::Exists(v, body)
+                }
+            },
+        }
+    }
+
+    fn fresh_letter(used: &BTreeSet<Var>) -> Var {
+        ('A'..=char::MAX)
+            .find(|c| c.is_uppercase() && ! used.contains(c))
+            .expect("ran out of letters")
+    }
+
+    // partial evaluation: replace the assigned letters by truth values and fold the constants away
+    pub fn assign(&self, partial_interpretation: &HashMap<Var, bool>) -> Formula {
+        match self {
+            Self::Letter(c) => {
+                match partial_interpretation.get(c) {
+                    Some(true) => Self::True,
+                    Some(false) => Self::False,
+                    None => Self::Letter(*c),
+                }
+            },
+            Self::True => Self::True,
+            Self::False => Self::False,
+            Self::Negation(sub) => Self::fold_negation(sub.assign(partial_interpretation)),
+            Self::SecondaryFunc { name, lhs, rhs } => {
+                Self::fold_secondary_func(*name, lhs.assign(partial_interpretation), rhs.assign(partial_interpretation))
+            },
+            Self::Forall(v, body) | Self::Exists(v, body) => {
+                let mut partial_interpretation = partial_interpretation.clone();
+                partial_interpretation.remove(v);
+                let body = body.assign(&partial_interpretation);
+                if ! body.free_letters().contains(v) {
+                    body
+                } else if let Self::Forall(..) = self {
+                    Self::Forall(*v, Box::new(body))
+                } else {
+                    Self::Exists(*v, Box::new(body))
+                }
+            },
+        }
+    }
+
+    // the cofactors of the Shannon expansion on v: (self with v = T, self with v = F)
+    pub fn cofactors(&self, v: Var) -> (Formula, Formula) {
+        (
+            self.assign(&HashMap::from([(v, true)])),
+            self.assign(&HashMap::from([(v, false)])),
+        )
+    }
+
+    fn fold_negation(sub: Formula) -> Formula {
+        match sub {
+            Self::True => Self::False,
+            Self::False => Self::True,
+            sub => Self::Negation(Box::new(sub)),
+        }
+    }
+
+    fn fold_secondary_func(name: SecondaryFuncName, lhs: Formula, rhs: Formula) -> Formula {
+        match (name, lhs, rhs) {
+            (SecondaryFuncName::Conjunction, Self::True, x) | (SecondaryFuncName::Conjunction, x, Self::True) => x,
+            (SecondaryFuncName::Conjunction, Self::False, _) | (SecondaryFuncName::Conjunction, _, Self::False) => Self::False,
+            (SecondaryFuncName::Disjunction, Self::False, x) | (SecondaryFuncName::Disjunction, x, Self::False) => x,
+            (SecondaryFuncName::Disjunction, Self::True, _) | (SecondaryFuncName::Disjunction, _, Self::True) => Self::True,
+            (SecondaryFuncName::Implicature, Self::True, x) => x,
+            (SecondaryFuncName::Implicature, Self::False, _) | (SecondaryFuncName::Implicature, _, Self::True) => Self::True,
+            (SecondaryFuncName::Implicature, x, Self::False) => Self::fold_negation(x),
+            (SecondaryFuncName::Equivalence, Self::True, x) | (SecondaryFuncName::Equivalence, x, Self::True) => x,
+            (SecondaryFuncName::Equivalence, Self::False, x) | (SecondaryFuncName::Equivalence, x, Self::False) => Self::fold_negation(x),
+            (name, lhs, rhs) => Self::SecondaryFunc { name, lhs: Box::new(lhs), rhs: Box::new(rhs) },
+        }
+    }
+
+    // the index of the main connective in to_sentence
+    pub fn main_connective_index(&self) -> usize {
+        match self {
+            Self::SecondaryFunc { lhs, .. } => {
+                let lhs_len = lhs.to_sentence().len();
+                if lhs.precedence() <= self.precedence() {
+                    lhs_len + 2 // brackets
+                } else {
+                    lhs_len
+                }
+            },
+            _ => 0,
+        }
+    }
+
+    // the offset in display columns of the main connective in the output of to_string_with
+    pub fn main_connective_offset(&self, symbols: SymbolSet) -> usize {
+        match self {
+            Self::SecondaryFunc { lhs, .. } => {
+                let lhs_len = lhs.to_string_with(symbols).width();
+                if lhs.precedence() <= self.precedence() {
+                    lhs_len + 3 // brackets and a space
+                } else {
+                    lhs_len + 1
+                }
+            },
+            _ => 0,
         }
     }
 
     fn precedence(&self) -> usize {
         match self {
+            Self::Forall(..)        => 0, // the scope extends as far right as possible
+            Self::Exists(..)        => 0,
             Self::SecondaryFunc { name, ..} => name.precedence(),
             Self::Negation(..)      => 4,
             Self::True              => 5,
@@ -111,7 +634,7 @@ impl Formula {
         }
     }
 
-    fn to_sentence(&self) -> Vec<Alphabet> {
+    pub fn to_sentence(&self) -> Vec<Alphabet> {
         match self {
             Self::Letter(c) => vec![Alphabet::Letter(*c)],
             Self::True => vec![Alphabet::True],
@@ -166,7 +689,17 @@ impl Formula {
                 v.push(func_symbol);
                 v.append(&mut rhs);
                 v
-            }
+            },
+            Self::Forall(x, body) => {
+                let mut v = vec![Alphabet::Forall(*x)];
+                v.append(&mut body.to_sentence());
+                v
+            },
+            Self::Exists(x, body) => {
+                let mut v = vec![Alphabet::Exists(*x)];
+                v.append(&mut body.to_sentence());
+                v
+            },
         }
     }
 
@@ -174,21 +707,33 @@ impl Formula {
     pub fn parse(sentence: &[crate::alphabet::Alphabet]) -> Result<Self, ()> {
         let mut pos = 0;
 
-        let (mut formula, bracketed) = Self::get_subformula(&sentence, &mut pos)?;
+        let formula = Self::get_sequence(sentence, &mut pos, false)?;
+
+        if pos < sentence.len() {
+            return Err(());
+        }
+
+        Ok(formula)
+    }
+
+    // read subformulas joined by secondary functions, up to the end of the sentence or a close bracket
+    fn get_sequence(sentence: &[Alphabet], pos: &mut usize, in_brackets: bool) -> Result<Self, ()> {
+        let (mut formula, bracketed) = Self::get_subformula(sentence, pos, in_brackets)?;
 
-        while pos < sentence.len() {
+        while *pos < sentence.len() && sentence[*pos] != Alphabet::CloseBracket {
             formula =
-            match &sentence[pos] {
+            match &sentence[*pos] {
                 Alphabet::SecondaryFunc(name) => {
-                    pos += 1;
-                    let (rhs, _bracketed) = Self::get_subformula(sentence, &mut pos)?;
+                    *pos += 1;
+                    let (rhs, _bracketed) = Self::get_subformula(sentence, pos, in_brackets)?;
                     if bracketed || formula.precedence() > name.precedence() {
                         Formula::SecondaryFunc {
                             name: *name,
                             lhs: Box::new(formula),
                             rhs: Box::new(rhs)
                         }
-                    } else if formula.precedence() == name.precedence() {
+                    } else if formula.precedence() == name.precedence() && ! in_brackets {
+                        // ambiguous at the top level; inside brackets, it groups to the right as it always has
                         return Err(());
                     } else { // ! bracketed && formula.precedence() < name.precedence. In this case, formula is SecondaryFunc
                         if let Self::SecondaryFunc { name: name0, lhs: lhs0, rhs: rhs0 } = formula {
@@ -215,7 +760,7 @@ impl Formula {
         Ok(formula)
     }
 
-    fn get_subformula(sentence: &[Alphabet], pos: &mut usize) -> Result<(Self, bool), ()> {
+    fn get_subformula(sentence: &[Alphabet], pos: &mut usize, in_brackets: bool) -> Result<(Self, bool), ()> {
         if *pos >= sentence.len() {
             return Err(());
         }
@@ -235,9 +780,19 @@ impl Formula {
             },
             Alphabet::Negation => {
                 *pos += 1;
-                let (subf, _bracketed) = Self::get_subformula(sentence, pos)?;
+                let (subf, _bracketed) = Self::get_subformula(sentence, pos, in_brackets)?;
                 Ok((Self::Negation(Box::new(subf)), false))
             },
+            Alphabet::Forall(v) => {
+                *pos += 1;
+                let body = Self::get_sequence(sentence, pos, in_brackets)?;
+                Ok((Self::Forall(v, Box::new(body)), false))
+            },
+            Alphabet::Exists(v) => {
+                *pos += 1;
+                let body = Self::get_sequence(sentence, pos, in_brackets)?;
+                Ok((Self::Exists(v, Box::new(body)), false))
+            },
             Alphabet::OpenBracket => {
                 Ok((Self::get_bracketed(sentence, pos)?, true))
             },
@@ -257,50 +812,12 @@ impl Formula {
         }
         *pos += 1;
 
-        let (mut formula, bracketed) = Self::get_subformula(sentence, pos)?;
+        let formula = Self::get_sequence(sentence, pos, true)?;
 
-        loop {
-            formula =
-            match &sentence[*pos] {
-                Alphabet::SecondaryFunc(name) => {
-                    *pos += 1;
-                    let (rhs, _bracketed) = Self::get_subformula(sentence, pos)?;
-                    if bracketed || formula.precedence() > name.precedence() {
-                        Formula::SecondaryFunc {
-                            name: *name,
-                            lhs: Box::new(formula),
-                            rhs: Box::new(rhs)
-                        }
-                    } else { // ! bracketed && formula.precedence() < name.precedence. In this case, formula is SecondaryFunc
-                        if let Self::SecondaryFunc { name: name0, lhs: lhs0, rhs: rhs0 } = formula {
-                            Formula::SecondaryFunc {
-                                name: name0,
-                                lhs: lhs0,
-                                rhs: Box::new(Formula::SecondaryFunc {
-                                    name: *name,
-                                    lhs: rhs0,
-                                    rhs: Box::new(rhs)
-                                })
-                            }
-                        } else {
-                            return Err(());
-                        }
-                    }
-                },
-                _ => {
-                    return Err(());
-                }
-            };
-
-            if *pos >= sentence.len() {
-                return Err(());
-            }
-
-            if sentence[*pos] == Alphabet::CloseBracket {
-                *pos += 1;
-                break;
-            }
+        if *pos >= sentence.len() || sentence[*pos] != Alphabet::CloseBracket {
+            return Err(());
         }
+        *pos += 1;
 
         Ok(formula)
     }
@@ -517,9 +1034,306 @@ mod fmt_tests {
     }
 }
 
+#[cfg(test)]
+mod alignment_tests {
+    use super::*;
+    use crate::test_util::formula;
+
+    #[test]
+    fn values_sit_under_wide_letters() {
+        // a fullwidth letter takes two columns
+        let table = formula("! Ｐ").truth_value_table();
+        assert_eq!(
+            table,
+            String::from(
+"Ｐ | ¬ Ｐ 
+F  | T F  
+T  | F T  
+"
+            )
+        );
+    }
+
+    #[test]
+    fn values_sit_under_quantifiers() {
+        let table = formula("∃P. P & Q").truth_value_table();
+        assert_eq!(
+            table,
+            String::from(
+"Q | ∃P. P ∧ Q 
+F | F   T F F 
+T | T   T T T 
+"
+            )
+        );
+    }
+
+    #[test]
+    fn eval_formula_columns_match() {
+        let f = formula("∀P. P | ! P").evaluate(&HashMap::new());
+        let s = f.to_string();
+        let (symbols, values) = s.split_once('\n').unwrap();
+        assert_eq!(symbols, "∀P. P ∨ ¬ P ");
+        assert_eq!(values,  "T   F T T F ");
+    }
+}
+
+#[cfg(test)]
+mod symbol_tests {
+    use super::*;
+    use crate::test_util::formula;
+
+    #[test]
+    fn symbol_sets() {
+        let f = formula("∀P. ! P & t -> ( P = f ) | Q");
+        assert_eq!(f.to_string_with(SymbolSet::Unicode), "∀P. ¬ P ∧ ⊤ → (P ↔ ⊥) ∨ Q");
+        assert_eq!(f.to_string_with(SymbolSet::Ascii), "forall P. ~ P & true -> (P <-> false) | Q");
+        assert_eq!(f.to_string_with(SymbolSet::Words), "forall P. not P and true implies (P iff false) or Q");
+        assert_eq!(f.to_string_with(SymbolSet::Latex), "\\forall P. \\neg P \\land \\top \\to (P \\leftrightarrow \\bot) \\lor Q");
+    }
+
+    #[test]
+    fn quantifier_words_parse() {
+        let f = formula("∀P. ∃Q. P = Q");
+        assert_eq!(formula("forall P. exists Q. P = Q"), f);
+        assert_eq!(formula("\\forall P. \\exists Q. P = Q"), f);
+        assert!(Alphabet::sentence("forall").is_err());
+        assert!(Alphabet::sentence("forall p.").is_err());
+    }
+
+    #[test]
+    fn ascii_table() {
+        let options = TableOptions { symbols: SymbolSet::Ascii, ..TableOptions::default() };
+        assert_eq!(
+            formula("P = ! Q").truth_value_table_with(&options),
+            String::from(
+"P Q | P <-> ~ Q 
+F F | F F   T F 
+F T | F T   F T 
+T F | T T   T F 
+T T | T F   F T 
+"
+            )
+        );
+    }
+}
+
+#[cfg(test)]
+mod label_tests {
+    use super::*;
+    use crate::test_util::formula;
+
+    #[test]
+    fn numeric_labels_true_first() {
+        let options = TableOptions { labels: Labels::numeric(), order: RowOrder::TrueFirst, ..TableOptions::default() };
+        assert_eq!(
+            formula("P & Q").truth_value_table_with(&options),
+            String::from(
+"P Q | P ∧ Q 
+1 1 | 1 1 1 
+1 0 | 1 0 0 
+0 1 | 0 0 1 
+0 0 | 0 0 0 
+"
+            )
+        );
+    }
+
+    #[test]
+    fn wide_labels_gray_code() {
+        let options = TableOptions { labels: Labels::new("yes", "no"), order: RowOrder::Gray, ..TableOptions::default() };
+        assert_eq!(
+            formula("P | Q").truth_value_table_with(&options),
+            String::from(
+"P   Q   | P   ∨   Q   
+no  no  | no  no  no  
+no  yes | no  yes yes 
+yes yes | yes yes yes 
+yes no  | yes yes no  
+"
+            )
+        );
+    }
+}
+
+#[cfg(test)]
+mod filter_tests {
+    use super::*;
+    use crate::test_util::formula;
+
+    #[test]
+    fn countermodels() {
+        let options = TableOptions { filter: RowFilter::countermodels(), ..TableOptions::default() };
+        assert_eq!(
+            formula("( P -> Q ) -> Q").truth_value_table_with(&options),
+            String::from(
+"P Q | ( P → Q ) → Q 
+F F |   F T F   F F 
+"
+            )
+        );
+    }
+
+    #[test]
+    fn partial_assignment() {
+        let filter = RowFilter { value: None, assignment: HashMap::from([('P', true), ('Z', false)]) };
+        let options = TableOptions { filter, ..TableOptions::default() };
+        assert_eq!(
+            formula("P & Q").truth_value_table_with(&options),
+            String::from(
+"P Q | P ∧ Q 
+T F | T F F 
+T T | T T T 
+"
+            )
+        );
+    }
+}
+
+#[cfg(test)]
+mod comparison_tests {
+    use super::*;
+    use crate::test_util::formula;
+
+    #[test]
+    fn differences_are_marked() {
+        let formulas = [formula("P -> Q"), formula("! P | Q"), formula("Q -> P")];
+        assert_eq!(
+            Formula::truth_value_table_of(&formulas, &TableOptions::default()),
+            String::from(
+"P Q | P → Q | ¬ P ∨ Q | Q → P | ≠ 
+F F | F T F | T F T F | F T F |   
+F T | F T T | T F T T | T F F | * 
+T F | T F F | F T F F | F T T | * 
+T T | T T T | F T T T | T T T |   
+"
+            )
+        );
+    }
+
+    #[test]
+    fn letters_are_the_union() {
+        let options = TableOptions { symbols: SymbolSet::Ascii, ..TableOptions::default() };
+        let table = Formula::truth_value_table_of(&[formula("P"), formula("Q")], &options);
+        assert!(table.starts_with("P Q | P | Q | != \nF F | F | F |    \n"));
+    }
+
+    #[test]
+    fn written_table_is_the_same() {
+        let f = formula("( P -> Q ) & R");
+        let mut out = vec![];
+        f.write_truth_value_table(&mut out, &TableOptions::default()).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), f.truth_value_table());
+    }
+
+    #[test]
+    fn threads_keep_the_row_order() {
+        // 2^13 rows, two batches for a single thread
+        let f = formula("( ( ( A -> B ) & ( C | D ) ) = ( ( E & F ) | ( G -> H ) ) ) & ( ( ( I | J ) -> ( K & L ) ) | M )");
+        let table = |threads, order| {
+            let options = TableOptions { threads, order, ..TableOptions::default() };
+            f.truth_value_table_with(&options)
+        };
+        for order in [RowOrder::FalseFirst, RowOrder::Gray] {
+            let expected = table(1, order);
+            assert_eq!(expected.lines().count(), (1 << 13) + 1);
+            assert_eq!(table(3, order), expected);
+        }
+    }
+
+    #[test]
+    fn single_formula_has_no_marker() {
+        let f = formula("P & Q");
+        assert_eq!(Formula::truth_value_table_of(std::slice::from_ref(&f), &TableOptions::default()), f.truth_value_table());
+    }
+}
+
+#[cfg(test)]
+mod eval_tests {
+    use super::*;
+    use crate::test_util::formula;
+
+    #[test]
+    fn agrees_with_evaluate() {
+        for s in ["( P -> Q ) & ! R", "( P = Q ) | ( ∀P. P -> Q )", "∃Q. ( P & Q ) | ∀R. R = Q"] {
+            let f = formula(s);
+            for (values, _) in f.rows() {
+                let letters: Vec<_> = f.free_letters().into_iter().collect();
+                let interpretation: HashMap<_, _> = letters.into_iter().zip(values).collect();
+                assert_eq!(f.eval(&interpretation), Ok(f.evaluate(&interpretation).is_true()), "{}", s);
+            }
+        }
+    }
+
+    #[test]
+    fn assignments() {
+        let f = formula("P -> Q");
+        assert_eq!(f.eval(&[('P', true), ('Q', false)]), Ok(false));
+        assert_eq!(f.eval(&BTreeMap::from([('P', false), ('Q', false)])), Ok(true));
+        let pairs = vec![('Q', true), ('P', true)];
+        assert_eq!(f.eval(pairs.as_slice()), Ok(true));
+    }
+
+    #[test]
+    fn unbound_variables() {
+        assert_eq!(formula("P & Q").eval(&[('P', false)]), Err(UnboundVariable('Q')));
+        assert_eq!(formula("∀P. P | Q").eval(&[('Q', true)]), Ok(true));
+        assert_eq!(formula("∀P. P | Q").eval(&[('P', true)]), Err(UnboundVariable('Q')));
+        assert_eq!(UnboundVariable('R').to_string(), "no truth value for R");
+    }
+
+    #[test]
+    fn try_evaluate() {
+        let f = formula("∀P. P | Q");
+        assert_eq!(f.try_evaluate(&[('P', true)]), Err(UnboundVariable('Q')));
+        let evaluated = f.try_evaluate(&[('Q', false)]).unwrap();
+        assert!(! evaluated.is_true());
+        assert_eq!(Ok(evaluated), f.try_evaluate(&HashMap::from([('Q', false)])));
+        assert_eq!(f.try_evaluate(&[('Q', true)]).map(|x| x.is_true()), f.eval(&[('Q', true)]));
+    }
+}
+
+#[cfg(test)]
+mod color_tests {
+    use super::*;
+    use crate::test_util::formula;
+
+    #[test]
+    fn uncolored_table_is_unchanged() {
+        let f = formula("P -> Q");
+        assert_eq!(f.truth_value_table_with(&TableOptions { color: false, ..TableOptions::default() }), f.truth_value_table());
+        assert!(! f.truth_value_table().contains('\x1b'));
+    }
+
+    #[test]
+    fn colored_table() {
+        let table = formula("! P").truth_value_table_with(&TableOptions { color: true, ..TableOptions::default() });
+        assert_eq!(
+            table,
+            String::from(
+"P | \x1b[1m¬\x1b[0m P 
+\x1b[31mF\x1b[0m | \x1b[1;32mT\x1b[0m \x1b[31mF\x1b[0m 
+\x1b[32mT\x1b[0m | \x1b[1;31mF\x1b[0m \x1b[32mT\x1b[0m 
+"
+            )
+        );
+    }
+
+    #[test]
+    fn colored_eval_formula() {
+        let f = formula("P & Q").evaluate(&HashMap::from([('P', true), ('Q', false)]));
+        assert_eq!(format!("{}", f), String::from("P ∧ Q \nT F F "));
+        assert_eq!(
+            format!("{:#}", f),
+            String::from("P \x1b[1m∧\x1b[0m Q \n\x1b[32mT\x1b[0m \x1b[1;31mF\x1b[0m \x1b[31mF\x1b[0m ")
+        );
+    }
+}
+
 #[cfg(test)]
 mod parse_test {
     use super::*;
+    use crate::test_util::sentence;
 
     #[test]
     fn parse_letters() {
@@ -601,6 +1415,126 @@ mod parse_test {
             ))
         )
     }
+
+    #[test]
+    fn same_precedence_in_brackets() {
+        let parse = |s: &str| Formula::parse(&sentence(s));
+        // inside brackets a chain groups to the right, at the top level it is ambiguous
+        assert_eq!(parse("( P & Q & R )"), parse("P & ( Q & R )"));
+        assert_eq!(parse("( P & Q | R )"), parse("P & ( Q | R )"));
+        assert_eq!(parse("( ∀P. P & Q & R )"), parse("∀P. P & ( Q & R )"));
+        assert!(parse("P & Q & R").is_err());
+        assert!(parse("P & Q | R").is_err());
+    }
+}
+
+#[cfg(test)]
+mod quantifier_tests {
+    use super::*;
+    use crate::formula::Formula::*;
+    use crate::test_util::sentence;
+
+    #[test]
+    fn parse_quantifiers() {
+        assert_eq!(
+            Formula::parse(&sentence("∀P. P | ! P")),
+            Ok(Forall('P', Box::new(SecondaryFunc {
+                name: SecondaryFuncName::Disjunction,
+                lhs: Box::new(Letter('P')),
+                rhs: Box::new(Negation(Box::new(Letter('P'))))
+            })))
+        );
+        assert_eq!(
+            Formula::parse(&sentence("Q & ( ∃P. P )")),
+            Ok(SecondaryFunc {
+                name: SecondaryFuncName::Conjunction,
+                lhs: Box::new(Letter('Q')),
+                rhs: Box::new(Exists('P', Box::new(Letter('P'))))
+            })
+        );
+    }
+
+    #[test]
+    fn fmt_quantifiers() {
+        let f = Formula::parse(&sentence("∀P. ∃Q. P = Q")).unwrap();
+        assert_eq!(format!("{}", f), String::from("∀P. ∃Q. P ↔ Q"));
+
+        let f = Formula::parse(&sentence("! ( ∀P. P ) -> Q")).unwrap();
+        assert_eq!(format!("{}", f), String::from("¬ (∀P. P) → Q"));
+    }
+
+    #[test]
+    fn evaluate_by_expansion() {
+        let f = Formula::parse(&sentence("∃P. P & Q")).unwrap();
+        let interpretation = HashMap::from([('Q', true)]);
+        assert!(f.evaluate(&interpretation).is_true());
+        let interpretation = HashMap::from([('Q', false)]);
+        assert!(! f.evaluate(&interpretation).is_true());
+    }
+
+    #[test]
+    fn table_has_only_free_letters() {
+        let f = Formula::parse(&sentence("∀P. P | Q")).unwrap();
+        let table = f.truth_value_table();
+        assert!(table.starts_with("Q |"));
+        assert_eq!(table.lines().count(), 3);
+    }
+
+    #[test]
+    fn solve_prenex() {
+        let f = Formula::parse(&sentence("∀P. ∃Q. P = Q")).unwrap();
+        assert_eq!(f.solve_qbf(), Ok(true));
+        let f = Formula::parse(&sentence("∃Q. ∀P. P = Q")).unwrap();
+        assert_eq!(f.solve_qbf(), Ok(false));
+    }
+
+    #[test]
+    fn solve_rejects_non_prenex_and_open() {
+        let f = Formula::parse(&sentence("! ( ∀P. P )")).unwrap();
+        assert!(f.solve_qbf().is_err());
+        let f = Formula::parse(&sentence("∀P. P | Q")).unwrap();
+        assert!(f.solve_qbf().is_err());
+    }
+}
+
+#[cfg(test)]
+mod substitution_tests {
+    use super::*;
+    use crate::test_util::formula;
+
+    #[test]
+    fn substitute_simultaneously() {
+        let map = HashMap::from([
+            ('P', formula("Q")),
+            ('Q', formula("P & R")),
+        ]);
+        assert_eq!(formula("P -> Q").substitute(&map), formula("Q -> P & R"));
+    }
+
+    #[test]
+    fn substitute_respects_binders() {
+        let map = HashMap::from([('P', formula("R"))]);
+        assert_eq!(formula("P & ( ∀P. P )").substitute(&map), formula("R & ( ∀P. P )"));
+
+        // Q would be captured by ∀Q., so the bound letter is renamed
+        let map = HashMap::from([('P', formula("Q"))]);
+        assert_eq!(formula("∀Q. P | Q").substitute(&map), formula("∀A. Q | A"));
+    }
+
+    #[test]
+    fn assign_folds_constants() {
+        let f = formula("( P & Q ) | ( R -> P )");
+        assert_eq!(f.assign(&HashMap::from([('P', true)])), formula("t"));
+        assert_eq!(f.assign(&HashMap::from([('P', false)])), formula("! R"));
+        assert_eq!(f.assign(&HashMap::from([('R', true)])), formula("( P & Q ) | P"));
+    }
+
+    #[test]
+    fn cofactors() {
+        let (if_true, if_false) = formula("P = Q").cofactors('P');
+        assert_eq!(if_true, formula("Q"));
+        assert_eq!(if_false, formula("! Q"));
+    }
 }
 
 // ∧
@@ -611,6 +1545,7 @@ mod parse_test {
 
 // an evaluated formula
 // stores a truth value for each subformula
+#[derive(PartialEq, Eq, Debug, Clone)]
 pub enum EvalFormula {
     Letter(char, bool),
     True,
@@ -622,55 +1557,212 @@ pub enum EvalFormula {
         rhs: Box<EvalFormula>,
         truth_value: bool,
     },
+    // the body is the instance deciding the truth value (see Formula::evaluate)
+    Forall(char, Box<EvalFormula>, bool),
+    Exists(char, Box<EvalFormula>, bool),
+}
+
+// the display width of a table column headed by symbol, which also has to fit a truth value
+pub(crate) fn column_width(symbol: &str, labels: &Labels) -> usize {
+    symbol.width().max(labels.width())
+}
+
+// a symbol or a truth value padded to the display width of its column, and a space.
+// with color, truth values are green or red and bold marks the main connective column.
+pub(crate) fn cell(text: &str, width: usize, value: Option<bool>, bold: bool, color: bool) -> String {
+    let pad = " ".repeat(width.saturating_sub(text.width()) + 1);
+    if ! color || text.is_empty() {
+        return format!("{}{}", text, pad);
+    }
+    let mut codes = vec![];
+    if bold {
+        codes.push("1");
+    }
+    match value {
+        Some(true) => codes.push("32"),
+        Some(false) => codes.push("31"),
+        None => (),
+    }
+    if codes.is_empty() {
+        return format!("{}{}", text, pad);
+    }
+    format!("\x1b[{}m{}\x1b[0m{}", codes.join(";"), text, pad)
+}
+
+// the order in which EvalFormula::nodes visits a formula
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum Traversal {
+    #[default]
+    PreOrder,  // each node before its children
+    PostOrder, // each node after its children
+}
+
+// the nodes of an evaluated formula with their positions, see EvalFormula::nodes
+pub struct Nodes<'a> {
+    order: Traversal,
+    // nodes still to visit, and whether their children have been pushed already
+    stack: Vec<(Vec<usize>, &'a EvalFormula, bool)>,
+}
+
+impl<'a> Iterator for Nodes<'a> {
+    type Item = (Vec<usize>, &'a EvalFormula);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (position, node, expanded) = self.stack.pop()?;
+            if expanded || self.order == Traversal::PreOrder && node.children().is_empty() {
+                return Some((position, node));
+            }
+            if self.order == Traversal::PostOrder {
+                self.stack.push((position.clone(), node, true));
+            }
+            for (i, child) in node.children().into_iter().enumerate().rev() {
+                let mut position = position.clone();
+                position.push(i);
+                self.stack.push((position, child, false));
+            }
+            if self.order == Traversal::PreOrder {
+                return Some((position, node));
+            }
+        }
+    }
 }
 
+fn label(b: Option<bool>, labels: &Labels) -> &str {
+    match b {
+        Some(b) => labels.get(b),
+        _ => ""
+    }
+}
+
+// the alternate flag {:#} turns on ANSI colors
 impl std::fmt::Display for EvalFormula {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_string_with(SymbolSet::Unicode, f.alternate()))
+    }
+}
+
+impl EvalFormula {
+    // the symbols, and the truth values under them on a second line
+    pub fn to_string_with(&self, symbols: SymbolSet, color: bool) -> String {
+        let labels = Labels::default();
+        let main = self.main_connective_index();
         let sentence = self.to_sentence();
         let mut s = (String::new(), String::new());
-        for (c, b) in sentence {
-            s.0.push_str(&format!("{:2}", c.to_string()));
-            let b =
-                match b {
-                    Some(true) => "T",
-                    Some(false) => "F",
-                    _ => ""
-                };
-            s.1.push_str(&format!("{:2}", b));
+        for (i, (c, b)) in sentence.into_iter().enumerate() {
+            let c = c.symbol(symbols);
+            let width = column_width(&c, &labels);
+            s.0.push_str(&cell(&c, width, None, i == main, color));
+            s.1.push_str(&cell(label(b, &labels), width, b, i == main, color));
         }
-        writeln!(f, "{}", s.0)?;
-        write!(f, "{}", s.1)
+        format!("{}\n{}", s.0, s.1)
     }
-}
 
-impl EvalFormula {
     // output only truth values
     pub fn to_string_tf(&self) -> String {
-        let sentence = self.to_sentence();
+        self.tf_string(&TableOptions::default())
+    }
+
+    fn tf_string(&self, options: &TableOptions) -> String {
+        let main = self.main_connective_index();
         let mut s = String::new();
-        for (_c, b) in sentence {
-            let b =
-                match b {
-                    Some(true) => "T",
-                    Some(false) => "F",
-                    _ => ""
-                };
-            s.push_str(&format!("{:2}", b));
+        for (i, (c, b)) in self.to_sentence().into_iter().enumerate() {
+            let width = column_width(&c.symbol(options.symbols), &options.labels);
+            s.push_str(&cell(label(b, &options.labels), width, b, i == main, options.color));
         }
         s
     }
 
-    fn is_true(&self) -> bool {
+    // the index of the main connective in to_sentence
+    fn main_connective_index(&self) -> usize {
+        match self {
+            Self::SecondaryFunc { lhs, .. } => {
+                let lhs_len = lhs.to_sentence().len();
+                if lhs.precidence() <= self.precidence() {
+                    lhs_len + 2 // brackets
+                } else {
+                    lhs_len
+                }
+            },
+            _ => 0,
+        }
+    }
+
+    // the truth value of this node
+    pub fn is_true(&self) -> bool {
         match self {
             Self::Letter(_, b) => *b,
             Self::True => true,
             Self::False => false,
             Self::Negation(_, b) => *b,
-            Self::SecondaryFunc { name: _, lhs: _, rhs: _, truth_value } => *truth_value
+            Self::SecondaryFunc { name: _, lhs: _, rhs: _, truth_value } => *truth_value,
+            Self::Forall(_, _, b) => *b,
+            Self::Exists(_, _, b) => *b,
+        }
+    }
+
+    // the direct subformulas, in the same order as Formula::children
+    pub fn children(&self) -> Vec<&EvalFormula> {
+        match self {
+            Self::Letter(..) | Self::True | Self::False => vec![],
+            Self::Negation(sub, _) => vec![sub],
+            Self::SecondaryFunc { lhs, rhs, .. } => vec![lhs, rhs],
+            Self::Forall(_, body, _) | Self::Exists(_, body, _) => vec![body],
+        }
+    }
+
+    // the subformula reached by following the child indices in position from the root
+    pub fn subformula_at(&self, position: &[usize]) -> Option<&EvalFormula> {
+        match position.split_first() {
+            None => Some(self),
+            Some((i, rest)) => self.children().get(*i)?.subformula_at(rest),
         }
     }
 
-    fn to_sentence(&self) -> Vec<(Alphabet, Option<bool>)> {
+    // every node with its position, which is also the position of the node it was evaluated from,
+    // so formula.subformula_at(&position) gives the originating Formula node
+    pub fn nodes(&self, order: Traversal) -> Nodes<'_> {
+        Nodes { order, stack: vec![(vec![], self, false)] }
+    }
+
+    // the formula this was evaluated from, without the truth values
+    pub fn to_formula(&self) -> Formula {
+        match self {
+            Self::Letter(c, _) => Formula::Letter(*c),
+            Self::True => Formula::True,
+            Self::False => Formula::False,
+            Self::Negation(sub, _) => Formula::Negation(Box::new(sub.to_formula())),
+            Self::SecondaryFunc { name, lhs, rhs, .. } =>
+                Formula::SecondaryFunc { name: *name, lhs: Box::new(lhs.to_formula()), rhs: Box::new(rhs.to_formula()) },
+            Self::Forall(v, body, _) => Formula::Forall(*v, Box::new(body.to_formula())),
+            Self::Exists(v, body, _) => Formula::Exists(*v, Box::new(body.to_formula())),
+        }
+    }
+
+    // the subformulas a false formula is false because of, with their positions.
+    // false conjunctions, disjunctions and quantifiers are blamed on their false parts,
+    // and any other false node, such as an implication or a negation, on itself.
+    pub fn false_subformulas(&self) -> Vec<(Vec<usize>, &EvalFormula)> {
+        if self.is_true() {
+            return vec![];
+        }
+        match self {
+            Self::SecondaryFunc { name: SecondaryFuncName::Conjunction | SecondaryFuncName::Disjunction, .. }
+            | Self::Forall(..) | Self::Exists(..) => {
+                let mut found = vec![];
+                for (i, child) in self.children().into_iter().enumerate() {
+                    for (mut position, node) in child.false_subformulas() {
+                        position.insert(0, i);
+                        found.push((position, node));
+                    }
+                }
+                found
+            },
+            _ => vec![(vec![], self)],
+        }
+    }
+
+    pub fn to_sentence(&self) -> Vec<(Alphabet, Option<bool>)> {
         let is_true = self.is_true();
         match self {
             Self::Letter(c, _) => vec![(Alphabet::Letter(*c), Some(is_true))],
@@ -725,15 +1817,27 @@ impl EvalFormula {
                 v.push((func_symbol, Some(is_true)));
                 v.append(&mut rhs);
                 v
-            }
+            },
+            Self::Forall(x, body, _) => {
+                let mut v = vec![(Alphabet::Forall(*x), Some(is_true))];
+                v.append(&mut body.to_sentence());
+                v
+            },
+            Self::Exists(x, body, _) => {
+                let mut v = vec![(Alphabet::Exists(*x), Some(is_true))];
+                v.append(&mut body.to_sentence());
+                v
+            },
         }
     }
 
     fn precidence(&self) -> usize {
         match self {
+            Self::Forall(..)        => 0, // the lowest precedence
+            Self::Exists(..)        => 0,
             Self::SecondaryFunc { name, ..} => {
                 match name {
-                    SecondaryFuncName::Equivalence => 1, // the lowest precedence
+                    SecondaryFuncName::Equivalence => 1,
                     SecondaryFuncName::Implicature => 2,
                     SecondaryFuncName::Conjunction => 3,
                     SecondaryFuncName::Disjunction => 3,
@@ -746,3 +1850,56 @@ impl EvalFormula {
         }
     }
 }
+
+
+#[cfg(test)]
+mod traversal_tests {
+    use super::*;
+    use crate::test_util::formula;
+
+    #[test]
+    fn orders() {
+        let f = formula("( P -> Q ) & ! R");
+        let evaluated = f.evaluate(&HashMap::from([('P', true), ('Q', false), ('R', false)]));
+        let pre: Vec<_> = evaluated.nodes(Traversal::PreOrder).map(|(position, node)| (position, node.to_formula().to_string())).collect();
+        assert_eq!(pre, vec![
+            (vec![], String::from("(P → Q) ∧ ¬ R")),
+            (vec![0], String::from("P → Q")),
+            (vec![0, 0], String::from("P")),
+            (vec![0, 1], String::from("Q")),
+            (vec![1], String::from("¬ R")),
+            (vec![1, 0], String::from("R")),
+        ]);
+        let post: Vec<_> = evaluated.nodes(Traversal::PostOrder).map(|(position, node)| (position, node.is_true())).collect();
+        assert_eq!(post, vec![
+            (vec![0, 0], true),
+            (vec![0, 1], false),
+            (vec![0], false),
+            (vec![1, 0], false),
+            (vec![1], true),
+            (vec![], false),
+        ]);
+    }
+
+    #[test]
+    fn positions_map_back() {
+        let f = formula("∀P. ( P | Q ) & R");
+        let evaluated = f.evaluate(&HashMap::from([('Q', false), ('R', true)]));
+        assert_eq!(evaluated.to_formula(), f);
+        for (position, node) in evaluated.nodes(Traversal::PostOrder) {
+            assert_eq!(f.subformula_at(&position), Some(&node.to_formula()));
+            assert_eq!(evaluated.subformula_at(&position), Some(node));
+        }
+    }
+
+    #[test]
+    fn blame() {
+        let f = formula("( P -> Q ) & ( R | ! P )");
+        let evaluated = f.evaluate(&HashMap::from([('P', true), ('Q', false), ('R', false)]));
+        let blamed: Vec<_> = evaluated.false_subformulas().into_iter()
+            .map(|(position, _)| f.subformula_at(&position).unwrap().to_string())
+            .collect();
+        assert_eq!(blamed, vec!["P → Q", "R", "¬ P"]);
+        assert!(f.evaluate(&HashMap::from([('P', false), ('Q', false), ('R', false)])).false_subformulas().is_empty());
+    }
+}
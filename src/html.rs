@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use crate::alphabet::SymbolSet;
+use crate::formula::Formula;
+use crate::table::{RowFilter, RowOrder, TruthTable};
+
+#[derive(Default)]
+pub struct HtmlOptions {
+    pub standalone: bool, // a whole page with a stylesheet instead of a <table> fragment
+    pub symbols: SymbolSet,
+    pub order: RowOrder, // the row order of Formula::to_html_table
+    pub filter: RowFilter, // the rows of Formula::to_html_table
+}
+
+const STYLE: &str = "\
+table.truth-table { border-collapse: collapse; font-family: monospace; }
+table.truth-table th, table.truth-table td { padding: 0.1em 0.4em; text-align: center; }
+table.truth-table thead { border-bottom: 1px solid; }
+table.truth-table .formula-start { border-left: 1px solid; }
+table.truth-table .main { font-weight: bold; background: #eef; }
+table.truth-table tr.false { background: #fdd; }
+";
+
+// the cells of a table before they are written out
+struct Layout {
+    title: String,
+    letter_cnt: usize,
+    header: Vec<String>,
+    main: usize, // index of the emphasised column among the formula columns
+    rows: Vec<(Vec<Option<bool>>, bool)>, // the cells, and whether the formula is true in this row
+}
+
+impl Formula {
+    // a table with a column per letter, then a column per symbol of the formula.
+    // the main connective column is emphasised and rows making the formula false are highlighted.
+    pub fn to_html_table(&self, options: &HtmlOptions) -> String {
+        let letters: Vec<_> = self.free_letters().into_iter().collect();
+        let mut table = TruthTable::with_order(vec![self.clone()], options.order);
+        table.retain_rows(&options.filter);
+        let rows = table.rows.iter()
+            .map(|row| {
+                let interpretation: HashMap<_, _> = letters.iter().copied().zip(row.interpretation.iter().copied()).collect();
+                let evaluated = self.evaluate(&interpretation);
+                let cells = row.interpretation.iter()
+                    .map(|b| Some(*b))
+                    .chain(evaluated.to_sentence().into_iter().map(|(_, b)| b))
+                    .collect();
+                (cells, evaluated.is_true())
+            })
+            .collect();
+
+        Layout {
+            title: self.to_string_with(options.symbols),
+            letter_cnt: letters.len(),
+            header: letters.iter()
+                .map(|c| c.to_string())
+                .chain(self.to_sentence().iter().map(|x| x.symbol(options.symbols)))
+                .collect(),
+            main: self.main_connective_index(),
+            rows,
+        }.to_html(options)
+    }
+}
+
+impl TruthTable {
+    // a table with a column per letter, then a column per formula.
+    // the last column is emphasised and rows where it is false are highlighted.
+    pub fn to_html(&self, options: &HtmlOptions) -> String {
+        Layout {
+            title: self.columns.last().map_or(String::new(), |column| column.to_string_with(options.symbols)),
+            letter_cnt: self.letters.len(),
+            header: self.letters.iter()
+                .map(|c| c.to_string())
+                .chain(self.columns.iter().map(|column| column.to_string_with(options.symbols)))
+                .collect(),
+            main: self.columns.len().saturating_sub(1),
+            rows: self.rows.iter()
+                .map(|row| {
+                    let cells = row.interpretation.iter().chain(row.values.iter()).map(|b| Some(*b)).collect();
+                    (cells, row.values.last().copied().unwrap_or(true))
+                })
+                .collect(),
+        }.to_html(options)
+    }
+}
+
+impl Layout {
+    fn class(&self, i: usize) -> &'static str {
+        match i.checked_sub(self.letter_cnt) {
+            Some(j) if j == self.main && j == 0 => " class=\"formula-start main\"",
+            Some(j) if j == self.main => " class=\"main\"",
+            Some(0) => " class=\"formula-start\"",
+            _ => "",
+        }
+    }
+
+    fn to_html(&self, options: &HtmlOptions) -> String {
+        let mut s = String::from("<table class=\"truth-table\">\n<thead>\n<tr>");
+        for (i, cell) in self.header.iter().enumerate() {
+            s.push_str(&format!("<th{}>{}</th>", self.class(i), escape(cell)));
+        }
+        s.push_str("</tr>\n</thead>\n<tbody>\n");
+        for (cells, is_true) in &self.rows {
+            s.push_str(if *is_true { "<tr>" } else { "<tr class=\"false\">" });
+            for (i, b) in cells.iter().enumerate() {
+                let value =
+                    match b {
+                        Some(true) => "T",
+                        Some(false) => "F",
+                        None => "",
+                    };
+                s.push_str(&format!("<td{}>{}</td>", self.class(i), value));
+            }
+            s.push_str("</tr>\n");
+        }
+        s.push_str("</tbody>\n</table>\n");
+
+        if ! options.standalone {
+            return s;
+        }
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+            escape(&self.title),
+            STYLE,
+            s
+        )
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+
+#[cfg(test)]
+mod html_tests {
+    use super::*;
+    use crate::test_util::formula;
+
+    #[test]
+    fn fragment() {
+        assert_eq!(
+            formula("P -> Q").to_html_table(&HtmlOptions::default()),
+            String::from(
+"<table class=\"truth-table\">
+<thead>
+<tr><th>P</th><th>Q</th><th class=\"formula-start\">P</th><th class=\"main\">→</th><th>Q</th></tr>
+</thead>
+<tbody>
+<tr><td>F</td><td>F</td><td class=\"formula-start\">F</td><td class=\"main\">T</td><td>F</td></tr>
+<tr><td>F</td><td>T</td><td class=\"formula-start\">F</td><td class=\"main\">T</td><td>T</td></tr>
+<tr class=\"false\"><td>T</td><td>F</td><td class=\"formula-start\">T</td><td class=\"main\">F</td><td>F</td></tr>
+<tr><td>T</td><td>T</td><td class=\"formula-start\">T</td><td class=\"main\">T</td><td>T</td></tr>
+</tbody>
+</table>
+"
+            )
+        );
+    }
+
+    #[test]
+    fn standalone_page() {
+        let page = formula("! P").to_html_table(&HtmlOptions { standalone: true, ..HtmlOptions::default() });
+        assert!(page.starts_with("<!DOCTYPE html>\n"));
+        assert!(page.contains("<title>¬ P</title>"));
+        assert!(page.contains("<th class=\"formula-start main\">¬</th>"));
+        assert!(page.ends_with("</table>\n</body>\n</html>\n"));
+    }
+
+    #[test]
+    fn subformula_table() {
+        let html = TruthTable::subformulas(&formula("P & ! P")).to_html(&HtmlOptions::default());
+        assert!(html.contains("<tr><th>P</th><th class=\"formula-start\">¬ P</th><th class=\"main\">P ∧ ¬ P</th></tr>"));
+        assert_eq!(html.matches("<tr class=\"false\">").count(), 2);
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(escape("<a & \"b\">"), "&lt;a &amp; &quot;b&quot;&gt;");
+    }
+}
@@ -0,0 +1,211 @@
+// JSON for formulas, evaluated formulas and truth tables.
+// formulas are written as nodes tagged with their "type", e.g.
+// {"type":"secondary_func","name":"conjunction","lhs":{"type":"letter","name":"P"},"rhs":{"type":"true"}}
+use std::collections::BTreeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use crate::formula::{EvalFormula, Formula, SecondaryFuncName, Var};
+use crate::table::TruthTable;
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Node {
+    Letter { name: Var },
+    True,
+    False,
+    Negation { operand: Box<Node> },
+    SecondaryFunc { name: SecondaryFuncName, lhs: Box<Node>, rhs: Box<Node> },
+    Forall { var: Var, body: Box<Node> },
+    Exists { var: Var, body: Box<Node> },
+}
+
+impl From<&Formula> for Node {
+    fn from(f: &Formula) -> Self {
+        match f {
+            Formula::Letter(c) => Self::Letter { name: *c },
+            Formula::True => Self::True,
+            Formula::False => Self::False,
+            Formula::Negation(sub) => Self::Negation { operand: Box::new(sub.as_ref().into()) },
+            Formula::SecondaryFunc { name, lhs, rhs } => Self::SecondaryFunc {
+                name: *name,
+                lhs: Box::new(lhs.as_ref().into()),
+                rhs: Box::new(rhs.as_ref().into()),
+            },
+            Formula::Forall(v, body) => Self::Forall { var: *v, body: Box::new(body.as_ref().into()) },
+            Formula::Exists(v, body) => Self::Exists { var: *v, body: Box::new(body.as_ref().into()) },
+        }
+    }
+}
+
+impl From<Node> for Formula {
+    fn from(node: Node) -> Self {
+        match node {
+            Node::Letter { name } => Self::Letter(name),
+            Node::True => Self::True,
+            Node::False => Self::False,
+            Node::Negation { operand } => Self::Negation(Box::new((*operand).into())),
+            Node::SecondaryFunc { name, lhs, rhs } => Self::SecondaryFunc {
+                name,
+                lhs: Box::new((*lhs).into()),
+                rhs: Box::new((*rhs).into()),
+            },
+            Node::Forall { var, body } => Self::Forall(var, Box::new((*body).into())),
+            Node::Exists { var, body } => Self::Exists(var, Box::new((*body).into())),
+        }
+    }
+}
+
+impl Serialize for Formula {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Node::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Formula {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Node::deserialize(deserializer).map(Self::from)
+    }
+}
+
+// the same nodes as Formula, each with the truth value it evaluated to
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum EvalNode {
+    Letter { name: Var, value: bool },
+    True { value: bool },
+    False { value: bool },
+    Negation { operand: Box<EvalNode>, value: bool },
+    SecondaryFunc { name: SecondaryFuncName, lhs: Box<EvalNode>, rhs: Box<EvalNode>, value: bool },
+    Forall { var: Var, body: Box<EvalNode>, value: bool },
+    Exists { var: Var, body: Box<EvalNode>, value: bool },
+}
+
+impl From<&EvalFormula> for EvalNode {
+    fn from(f: &EvalFormula) -> Self {
+        let value = f.is_true();
+        match f {
+            EvalFormula::Letter(c, _) => Self::Letter { name: *c, value },
+            EvalFormula::True => Self::True { value },
+            EvalFormula::False => Self::False { value },
+            EvalFormula::Negation(sub, _) => Self::Negation { operand: Box::new(sub.as_ref().into()), value },
+            EvalFormula::SecondaryFunc { name, lhs, rhs, .. } => Self::SecondaryFunc {
+                name: *name,
+                lhs: Box::new(lhs.as_ref().into()),
+                rhs: Box::new(rhs.as_ref().into()),
+                value,
+            },
+            EvalFormula::Forall(v, body, _) => Self::Forall { var: *v, body: Box::new(body.as_ref().into()), value },
+            EvalFormula::Exists(v, body, _) => Self::Exists { var: *v, body: Box::new(body.as_ref().into()), value },
+        }
+    }
+}
+
+impl Serialize for EvalFormula {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        EvalNode::from(self).serialize(serializer)
+    }
+}
+
+#[derive(Serialize)]
+struct Column<'a> {
+    text: String,
+    formula: &'a Formula,
+}
+
+#[derive(Serialize)]
+struct Row<'a> {
+    interpretation: BTreeMap<Var, bool>,
+    values: &'a [bool],
+}
+
+impl Serialize for TruthTable {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Table<'a> {
+            variables: &'a [Var],
+            columns: Vec<Column<'a>>,
+            rows: Vec<Row<'a>>,
+        }
+
+        Table {
+            variables: &self.letters,
+            columns: self.columns.iter()
+                .map(|formula| Column { text: formula.to_string(), formula })
+                .collect(),
+            rows: self.rows.iter()
+                .map(|row| Row {
+                    interpretation: self.letters.iter().copied().zip(row.interpretation.iter().copied()).collect(),
+                    values: &row.values,
+                })
+                .collect(),
+        }.serialize(serializer)
+    }
+}
+
+impl Formula {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(s)
+    }
+}
+
+impl EvalFormula {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+impl TruthTable {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+
+#[cfg(test)]
+mod json_tests {
+    use std::collections::HashMap;
+    use super::*;
+    use crate::test_util::formula;
+
+    #[test]
+    fn formula_as_tagged_nodes() {
+        assert_eq!(
+            formula("! P & t").to_json(),
+            String::from(r#"{"type":"secondary_func","name":"conjunction","lhs":{"type":"negation","operand":{"type":"letter","name":"P"}},"rhs":{"type":"true"}}"#)
+        );
+    }
+
+    #[test]
+    fn formula_round_trip() {
+        for s in ["( P -> Q ) = ! R", "∀P. ∃Q. P | ( Q & f )"] {
+            let f = formula(s);
+            assert_eq!(Formula::from_json(&f.to_json()).unwrap(), f);
+        }
+        assert!(Formula::from_json(r#"{"type":"letter"}"#).is_err());
+    }
+
+    #[test]
+    fn eval_formula_has_values() {
+        let f = formula("! P").evaluate(&HashMap::from([('P', false)]));
+        assert_eq!(
+            f.to_json(),
+            String::from(r#"{"type":"negation","operand":{"type":"letter","name":"P","value":false},"value":true}"#)
+        );
+    }
+
+    #[test]
+    fn table() {
+        let table = TruthTable::new(vec![formula("! P")]);
+        assert_eq!(
+            table.to_json(),
+            String::from(concat!(
+                r#"{"variables":["P"],"#,
+                r#""columns":[{"text":"¬ P","formula":{"type":"negation","operand":{"type":"letter","name":"P"}}}],"#,
+                r#""rows":[{"interpretation":{"P":false},"values":[true]},{"interpretation":{"P":true},"values":[false]}]}"#
+            ))
+        );
+    }
+}
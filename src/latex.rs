@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use crate::alphabet::SymbolSet;
+use crate::formula::Formula;
+use crate::table::{RowFilter, RowOrder, TruthTable};
+
+#[derive(Default)]
+pub struct LatexOptions {
+    pub highlight: bool, // set the main connective column in bold
+    pub order: RowOrder, // the row order of Formula::to_latex_table
+    pub filter: RowFilter, // the rows of Formula::to_latex_table
+}
+
+impl Formula {
+    // the formula in LaTeX math mode, without the surrounding $
+    pub fn to_latex(&self) -> String {
+        self.to_string_with(SymbolSet::Latex)
+    }
+
+    // a tabular with a column per letter, a vertical rule, then a column per symbol of the formula
+    pub fn to_latex_table(&self, options: &LatexOptions) -> String {
+        let letters: Vec<_> = self.free_letters().into_iter().collect();
+        let sentence = self.to_sentence();
+        let main = self.main_connective_index();
+
+        let mut s = format!("\\begin{{tabular}}{{{}|{}}}\n", "c".repeat(letters.len()), "c".repeat(sentence.len()));
+        let header: Vec<_> = letters.iter()
+            .map(|c| format!("${}$", c))
+            .chain(sentence.iter().enumerate().map(|(i, x)| {
+                let cell = format!("${}$", x.symbol(SymbolSet::Latex));
+                if options.highlight && i == main {
+                    format!("\\boldmath{}", cell)
+                } else {
+                    cell
+                }
+            }))
+            .collect();
+        s.push_str(&format!("{} \\\\\n\\hline\n", header.join(" & ")));
+
+        let mut table = TruthTable::with_order(vec![self.clone()], options.order);
+        table.retain_rows(&options.filter);
+        for row in &table.rows {
+            let interpretation: HashMap<_, _> = letters.iter().copied().zip(row.interpretation.iter().copied()).collect();
+            let cells: Vec<_> = row.interpretation.iter()
+                .map(|b| Some(*b))
+                .chain(self.evaluate(&interpretation).to_sentence().into_iter().map(|(_, b)| b))
+                .enumerate()
+                .map(|(i, b)| {
+                    let cell = value(b);
+                    if options.highlight && i == letters.len() + main && ! cell.is_empty() {
+                        format!("\\textbf{{{}}}", cell)
+                    } else {
+                        cell.to_string()
+                    }
+                })
+                .collect();
+            s.push_str(&format!("{} \\\\\n", cells.join(" & ")));
+        }
+        s.push_str("\\end{tabular}\n");
+        s
+    }
+}
+
+impl TruthTable {
+    // a tabular with a column per letter, a vertical rule, then a column per formula.
+    // the highlighted column is the last one, which is the whole formula for TruthTable::subformulas.
+    pub fn to_latex(&self, options: &LatexOptions) -> String {
+        let mut s = format!("\\begin{{tabular}}{{{}|{}}}\n", "c".repeat(self.letters.len()), "c".repeat(self.columns.len()));
+        let last = (self.letters.len() + self.columns.len()).saturating_sub(1);
+
+        let header: Vec<_> = self.letters.iter()
+            .map(|c| format!("${}$", c))
+            .chain(self.columns.iter().map(|column| format!("${}$", column.to_latex())))
+            .enumerate()
+            .map(|(i, cell)| if options.highlight && i == last { format!("\\boldmath{}", cell) } else { cell })
+            .collect();
+        s.push_str(&format!("{} \\\\\n\\hline\n", header.join(" & ")));
+
+        for row in &self.rows {
+            let cells: Vec<_> = row.interpretation.iter()
+                .chain(row.values.iter())
+                .enumerate()
+                .map(|(i, b)| {
+                    let cell = value(Some(*b));
+                    if options.highlight && i == last { format!("\\textbf{{{}}}", cell) } else { cell.to_string() }
+                })
+                .collect();
+            s.push_str(&format!("{} \\\\\n", cells.join(" & ")));
+        }
+        s.push_str("\\end{tabular}\n");
+        s
+    }
+}
+
+fn value(b: Option<bool>) -> &'static str {
+    match b {
+        Some(true) => "T",
+        Some(false) => "F",
+        None => "",
+    }
+}
+
+
+#[cfg(test)]
+mod latex_tests {
+    use super::*;
+    use crate::test_util::formula;
+
+    #[test]
+    fn latex_formula() {
+        assert_eq!(
+            formula("! ( P & Q ) -> ( R = t ) | f").to_latex(),
+            String::from("\\neg (P \\land Q) \\to (R \\leftrightarrow \\top) \\lor \\bot")
+        );
+        assert_eq!(formula("∀P. ∃Q. P | Q").to_latex(), String::from("\\forall P. \\exists Q. P \\lor Q"));
+    }
+
+    #[test]
+    fn latex_table_highlights_main_connective() {
+        let options = LatexOptions { highlight: true, ..LatexOptions::default() };
+        assert_eq!(
+            formula("! ( P & Q )").to_latex_table(&options),
+            String::from(
+"\\begin{tabular}{cc|cccccc}
+$P$ & $Q$ & \\boldmath$\\neg$ & $($ & $P$ & $\\land$ & $Q$ & $)$ \\\\
+\\hline
+F & F & \\textbf{T} &  & F & F & F &  \\\\
+F & T & \\textbf{T} &  & F & F & T &  \\\\
+T & F & \\textbf{T} &  & T & F & F &  \\\\
+T & T & \\textbf{F} &  & T & T & T &  \\\\
+\\end{tabular}
+"
+            )
+        );
+    }
+
+    #[test]
+    fn latex_subformula_table() {
+        let table = TruthTable::subformulas(&formula("! P | Q"));
+        assert_eq!(
+            table.to_latex(&LatexOptions::default()),
+            String::from(
+"\\begin{tabular}{cc|cc}
+$P$ & $Q$ & $\\neg P$ & $\\neg P \\lor Q$ \\\\
+\\hline
+F & F & T & T \\\\
+F & T & T & T \\\\
+T & F & F & F \\\\
+T & T & F & T \\\\
+\\end{tabular}
+"
+            )
+        );
+    }
+}
@@ -0,0 +1,23 @@
+#![allow(dead_code)]
+// parse errors are reported as Err(()), the caller knowing what it was parsing
+#![allow(clippy::result_unit_err)]
+
+pub mod alphabet;
+pub mod formula;
+pub mod compile;
+pub mod parallel;
+pub mod simplify;
+pub mod visit;
+pub mod store;
+pub mod three_valued;
+pub mod derivation;
+pub mod table;
+pub mod argument;
+pub mod csv;
+pub mod markdown;
+pub mod latex;
+pub mod html;
+#[cfg(feature = "serde")]
+pub mod json;
+#[cfg(test)]
+mod test_util;
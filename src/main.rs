@@ -1,39 +1,252 @@
-#![allow(dead_code)]
+use std::io::IsTerminal;
+use truth_value_table::formula::{Alphabet, Formula, Labels, RowFilter, RowOrder, SymbolSet, TableOptions};
+use truth_value_table::derivation::{Derivation, Target};
+use truth_value_table::argument::Argument;
+use truth_value_table::table::{parse_assignment, TruthTable};
+use truth_value_table::csv::CsvOptions;
+use truth_value_table::markdown::MarkdownOptions;
+use truth_value_table::latex::LatexOptions;
+use truth_value_table::html::HtmlOptions;
+use truth_value_table::three_valued::Logic;
 
-mod alphabet;
-mod formula;
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum Format {
+    Text,
+    Csv,
+    Tsv,
+    Markdown,
+    Latex,
+    Html,
+    #[cfg(feature = "serde")]
+    Json,
+}
 
-use formula::{Alphabet, Formula};
+const USAGE: &str = "usage: truth-value-table [--derive=nnf|cnf|simplify] [--subformulas] [--compare] [--argument] [--format=text|csv|tsv|markdown|latex|html|json] [--values=tf|10|symbols|TRUE,FALSE[,UNKNOWN]] [--order=false-first|true-first|gray] [--rows=all|models|countermodels] [--where=P=T,Q=F,...] [--code] [--highlight] [--standalone] [--color=auto|always|never] [--symbols=unicode|ascii|words|latex] [--threads=N] [--logic=kleene|bochvar|lukasiewicz]";
 
 fn main() {
-    println!();
-    println!("===   TRUTH VALUE TABLE GENERATOR ===");
-    println!();
-    println!("Available alphabets:");
-    println!("  A, B, ..., Z, t, f, !, &, |, ->, =, (, )");
+    let mut derive = None;
+    let mut subformulas = false;
+    let mut compare = false;
+    let mut argument = false;
+    let mut format = Format::Text;
+    let mut numeric = false;
+    let mut labels = Labels::default();
+    let mut order = RowOrder::FalseFirst;
+    let mut filter = RowFilter::default();
+    let mut threads = 0;
+    let mut code = false;
+    let mut highlight = false;
+    let mut standalone = false;
+    let mut color = None;
+    let mut symbols = SymbolSet::Unicode;
+    let mut logic = None;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--subformulas" => subformulas = true,
+            "--compare" => compare = true,
+            "--argument" => argument = true,
+            "--derive=nnf" => derive = Some(Target::Nnf),
+            "--derive=cnf" => derive = Some(Target::Cnf),
+            "--derive=simplify" => derive = Some(Target::Simplified),
+            "--format=text" => format = Format::Text,
+            "--format=csv" => format = Format::Csv,
+            "--format=tsv" => format = Format::Tsv,
+            "--format=markdown" => format = Format::Markdown,
+            "--format=latex" => format = Format::Latex,
+            "--format=html" => format = Format::Html,
+            #[cfg(feature = "serde")]
+            "--format=json" => format = Format::Json,
+            "--code" => code = true,
+            "--highlight" => highlight = true,
+            "--standalone" => standalone = true,
+            "--color=auto" => color = None,
+            "--color=always" => color = Some(true),
+            "--color=never" => color = Some(false),
+            // custom labels are used by the text table only; the other formats write T/F or 1/0
+            x if x.starts_with("--values=") && Labels::try_from(&x["--values=".len()..]).is_ok() => {
+                labels = Labels::try_from(&x["--values=".len()..]).unwrap();
+                numeric = labels == Labels::numeric();
+            },
+            x if x.starts_with("--order=") && RowOrder::try_from(&x["--order=".len()..]).is_ok() => {
+                order = RowOrder::try_from(&x["--order=".len()..]).unwrap();
+            },
+            "--rows=all" => filter.value = None,
+            "--rows=models" => filter.value = Some(true),
+            "--rows=countermodels" => filter.value = Some(false),
+            x if x.starts_with("--where=") && parse_assignment(&x["--where=".len()..]).is_ok() => {
+                filter.assignment = parse_assignment(&x["--where=".len()..]).unwrap();
+            },
+            // 0 uses one thread per available core
+            x if x.starts_with("--threads=") && x["--threads=".len()..].parse::<usize>().is_ok() => {
+                threads = x["--threads=".len()..].parse().unwrap();
+            },
+            x if x.starts_with("--symbols=") && SymbolSet::try_from(&x["--symbols=".len()..]).is_ok() => {
+                symbols = SymbolSet::try_from(&x["--symbols=".len()..]).unwrap();
+            },
+            // a three-valued table of a single formula, written as text only
+            x if x.starts_with("--logic=") && Logic::try_from(&x["--logic=".len()..]).is_ok() => {
+                logic = Some(Logic::try_from(&x["--logic=".len()..]).unwrap());
+            },
+            _ => {
+                eprintln!("unknown option: {}", arg);
+                eprintln!("{}", USAGE);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    if logic.is_some() && format != Format::Text {
+        eprintln!("--logic is only supported with --format=text");
+        eprintln!("{}", USAGE);
+        std::process::exit(2);
+    }
+    // only the text table has the column marking where the formulas differ
+    if compare && format != Format::Text {
+        eprintln!("--compare is only supported with --format=text");
+        eprintln!("{}", USAGE);
+        std::process::exit(2);
+    }
+
+    // by default, color only a terminal, unless NO_COLOR is set
+    let color = color.unwrap_or_else(|| std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none());
+    let table_options = TableOptions { color, symbols, labels, order, filter: filter.clone(), threads };
+
+    // keep stdout clean for machine-readable output
+    let say = |s: &str| {
+        if format == Format::Text {
+            println!("{}", s);
+        } else {
+            eprintln!("{}", s);
+        }
+    };
+
+    // every format but the text table of a single formula is written from a TruthTable
+    let print_table = |table: &TruthTable| {
+        match format {
+            Format::Text => print!("{}", table.to_string_with(&table_options)),
+            Format::Csv => print!("{}", table.to_csv(&CsvOptions { numeric, symbols, ..CsvOptions::default() })),
+            Format::Tsv => print!("{}", table.to_csv(&CsvOptions { numeric, symbols, ..CsvOptions::tsv() })),
+            Format::Markdown => print!("{}", table.to_markdown(&MarkdownOptions { code, numeric, symbols })),
+            Format::Latex => print!("{}", table.to_latex(&LatexOptions { highlight, ..LatexOptions::default() })),
+            Format::Html => print!("{}", table.to_html(&HtmlOptions { standalone, symbols, ..HtmlOptions::default() })),
+            #[cfg(feature = "serde")]
+            Format::Json => println!("{}", table.to_json()),
+        }
+    };
+
+    say("");
+    say("===   TRUTH VALUE TABLE GENERATOR ===");
+    say("");
+    say("Available alphabets:");
+    say(&format!(
+        "  A, B, ..., Z, t, f, !, &, |, ->, =, (, ), {}, {}, ...",
+        Alphabet::Forall('A').symbol(symbols),
+        Alphabet::Exists('A').symbol(symbols)
+    ));
 
     loop {
-        println!();
-        println!();
-        println!("enter a formula (alphabets separated by a space), or 'q' to quit:");
+        say("");
+        say("");
+        say("enter a formula (alphabets separated by a space), or 'q' to quit:");
+        if subformulas {
+            say("(to choose the columns, follow it with ' ; ' and the subformulas separated by ' ; ')");
+        }
+        if compare {
+            say("(to compare formulas, separate them by ' ; ')");
+        }
+        if argument {
+            say("(enter an argument as premises separated by ' , ', then '|-' and the conclusion)");
+        }
 
         let mut input = String::new();
-        std::io::stdin().read_line(&mut input).expect("failed to read input");
-        if input == "q\n" {
-            println!("Bye!");
+        let n = std::io::stdin().read_line(&mut input).expect("failed to read input");
+        if n == 0 || input == "q\n" {
+            say("Bye!");
             break;
         }
-        println!();
+        say("");
+
+        if argument {
+            let argument = Argument::parse(&input).expect("failed to parse the argument");
+            if format == Format::Text {
+                print!("{}", argument.to_table(&table_options));
+            } else {
+                let mut table = TruthTable::with_order(argument.formulas(), order);
+                table.retain_rows(&filter);
+                print_table(&table);
+                say(&argument.verdict(&table_options));
+            }
+            continue;
+        }
+
+        let mut parts = input.split(';');
+        let formula = parse_str(parts.next().unwrap()).expect("failed to parse the sentence");
+
+        if let Some(target) = derive {
+            print!("{}", Derivation::new(&formula, target).to_string_with(symbols));
+            continue;
+        }
+        if let Some(logic) = logic {
+            // such as a formula with more than 80 letters
+            if let Err(e) = formula.write_three_valued_table(&mut std::io::stdout().lock(), &logic, &table_options) {
+                eprintln!("{}", e);
+            }
+            continue;
+        }
+        if compare {
+            let mut formulas = vec![formula];
+            formulas.extend(parts.map(|s| parse_str(s).expect("failed to parse the formula")));
+            Formula::write_truth_value_table_of(&formulas, &mut std::io::stdout().lock(), &table_options)
+                .expect("failed to write the table");
+            continue;
+        }
+        if ! subformulas {
+            match format {
+                Format::Text => {
+                    // written as it is evaluated, as a table with many letters may not fit in memory
+                    formula.write_truth_value_table(&mut std::io::stdout().lock(), &table_options)
+                        .expect("failed to write the table");
+                    continue;
+                },
+                Format::Latex => {
+                    print!("{}", formula.to_latex_table(&LatexOptions { highlight, order, filter: filter.clone() }));
+                    continue;
+                },
+                Format::Html => {
+                    print!("{}", formula.to_html_table(&HtmlOptions { standalone, symbols, order, filter: filter.clone() }));
+                    continue;
+                },
+                _ => (),
+            }
+        }
 
-        let formula = parse_str(&input).expect("failed to parse the sentence");
+        let mut table =
+            if subformulas {
+                let selected: Vec<_> = parts.map(|s| parse_str(s).expect("failed to parse the subformula")).collect();
+                if selected.is_empty() {
+                    TruthTable::subformulas_with_order(&formula, order)
+                } else {
+                    match TruthTable::selected_subformulas(&formula, &selected, order) {
+                        Ok(table) => table,
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            continue;
+                        },
+                    }
+                }
+            } else {
+                TruthTable::with_order(vec![formula], order)
+            };
+        // the last column decides which rows are models
+        table.retain_rows(&filter);
 
-        print!("{}", formula.truth_value_table());
+        print_table(&table);
     }
 }
 
 
 fn parse_str(s: &str) -> Result<Formula, ()> {
-    let sentence: Vec<_> = s.split_whitespace().map(|x| Alphabet::try_from(x).expect("invalid alphabet")).collect();
+    let sentence = Alphabet::sentence(s).expect("invalid alphabet");
 
     Formula::parse(&sentence)
 }
@@ -0,0 +1,128 @@
+use unicode_width::UnicodeWidthStr;
+use crate::alphabet::SymbolSet;
+use crate::table::TruthTable;
+
+#[derive(Default)]
+pub struct MarkdownOptions {
+    pub code: bool,    // write the column formulas as inline code instead of plain glyphs
+    pub numeric: bool, // write 1/0 instead of T/F
+    pub symbols: SymbolSet,
+}
+
+impl TruthTable {
+    // a GitHub-flavored Markdown table, padded so that the source lines up as well
+    pub fn to_markdown(&self, options: &MarkdownOptions) -> String {
+        let value = |b: bool|
+            match (b, options.numeric) {
+                (true, false) => "T",
+                (false, false) => "F",
+                (true, true) => "1",
+                (false, true) => "0",
+            };
+
+        let header: Vec<_> = self.letters.iter()
+            .map(|c| c.to_string())
+            .chain(self.columns.iter().map(|column| {
+                // GFM splits cells at pipes even inside code spans
+                if options.code {
+                    format!("`{}`", escape(&column.to_string_with(options.symbols)))
+                } else {
+                    escape(&column.to_string_with(options.symbols))
+                }
+            }))
+            .collect();
+        // the delimiter row needs at least three characters
+        let widths: Vec<_> = header.iter().map(|cell| cell.width().max(3)).collect();
+
+        let mut s = line(&header, &widths);
+        let delimiters: Vec<_> = widths.iter().map(|w| format!(":{}:", "-".repeat(w - 2))).collect();
+        s.push_str(&line(&delimiters, &widths));
+        for row in &self.rows {
+            let cells: Vec<_> = row.interpretation.iter()
+                .chain(row.values.iter())
+                .map(|b| value(*b).to_string())
+                .collect();
+            s.push_str(&line(&cells, &widths));
+        }
+        s
+    }
+}
+
+// one table line with every cell centered in its column
+fn line(cells: &[String], widths: &[usize]) -> String {
+    let mut s = String::from("|");
+    for (cell, width) in cells.iter().zip(widths) {
+        let pad = width - cell.width();
+        s.push(' ');
+        s.push_str(&" ".repeat(pad / 2));
+        s.push_str(cell);
+        s.push_str(&" ".repeat(pad - pad / 2));
+        s.push_str(" |");
+    }
+    s.push('\n');
+    s
+}
+
+// a pipe would end the cell early
+fn escape(cell: &str) -> String {
+    cell.replace('|', "\\|")
+}
+
+
+#[cfg(test)]
+mod markdown_tests {
+    use super::*;
+    use crate::test_util::formula;
+
+    #[test]
+    fn glyph_header() {
+        let table = TruthTable::new(vec![formula("P -> Q")]);
+        assert_eq!(
+            table.to_markdown(&MarkdownOptions::default()),
+            String::from(
+"|  P  |  Q  | P → Q |
+| :-: | :-: | :---: |
+|  F  |  F  |   T   |
+|  F  |  T  |   T   |
+|  T  |  F  |   F   |
+|  T  |  T  |   T   |
+"
+            )
+        );
+    }
+
+    #[test]
+    fn code_header_and_numeric_values() {
+        let table = TruthTable::new(vec![formula("! P")]);
+        let options = MarkdownOptions { code: true, numeric: true, ..MarkdownOptions::default() };
+        assert_eq!(
+            table.to_markdown(&options),
+            String::from(
+"|  P  | `¬ P` |
+| :-: | :---: |
+|  0  |   1   |
+|  1  |   0   |
+"
+            )
+        );
+    }
+
+    #[test]
+    fn ascii_header_is_escaped() {
+        let table = TruthTable::new(vec![formula("P | Q")]);
+        let options = MarkdownOptions { symbols: SymbolSet::Ascii, ..MarkdownOptions::default() };
+        assert!(table.to_markdown(&options).starts_with("|  P  |  Q  | P \\| Q |\n"));
+    }
+
+    #[test]
+    fn ascii_code_header_is_escaped() {
+        let table = TruthTable::new(vec![formula("P | Q")]);
+        let options = MarkdownOptions { code: true, symbols: SymbolSet::Ascii, ..MarkdownOptions::default() };
+        assert!(table.to_markdown(&options).starts_with("|  P  |  Q  | `P \\| Q` |\n"));
+    }
+
+    #[test]
+    fn pipes_are_escaped() {
+        assert_eq!(escape("P | Q"), "P \\| Q");
+    }
+}
@@ -0,0 +1,140 @@
+use std::num::NonZeroUsize;
+
+// the number of threads to use, 0 meaning one per available core
+pub fn threads(requested: usize) -> usize {
+    match requested {
+        0 => std::thread::available_parallelism().map_or(1, NonZeroUsize::get),
+        n => n,
+    }
+}
+
+// split first..=last into consecutive ranges, in order, one per thread,
+// without giving a thread fewer than min items unless there are fewer in total
+pub fn split(first: u128, last: u128, threads: usize, min: u128) -> Vec<(u128, u128)> {
+    let cnt = (last - first).saturating_add(1);
+    let parts = (threads.max(1) as u128).min(cnt.div_ceil(min.max(1))).max(1);
+    let size = cnt.div_ceil(parts);
+    let mut ranges = vec![];
+    let mut start = first;
+    loop {
+        let end = start.saturating_add(size - 1).min(last);
+        ranges.push((start, end));
+        if end == last {
+            break;
+        }
+        start = end + 1;
+    }
+    ranges
+}
+
+// the results of f over each range, in order, computed on a thread per range
+pub fn map<T: Send>(ranges: &[(u128, u128)], f: impl Fn(u128, u128) -> T + Sync) -> Vec<T> {
+    if let [(first, last)] = ranges {
+        return vec![f(*first, *last)];
+    }
+    let f = &f;
+    std::thread::scope(|s| {
+        let handles: Vec<_> = ranges.iter().map(|(first, last)| s.spawn(move || f(*first, *last))).collect();
+        handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+    })
+}
+
+// f over consecutive chunks of first..=last, of chunk items each, handing the results to consume in order.
+// the threads are started once, thread w taking chunks w, w + threads, ... and keeping at most one
+// finished chunk waiting, so results can be written as they come without holding the whole range.
+// the threads stop once consume fails.
+pub fn for_each_chunk<T: Send, E>(
+    first: u128,
+    last: u128,
+    chunk: u128,
+    threads: usize,
+    f: impl Fn(u128, u128) -> T + Sync,
+    mut consume: impl FnMut(T) -> Result<(), E>,
+) -> Result<(), E> {
+    let chunk = chunk.max(1);
+    let chunk_cnt = (last - first) / chunk + 1;
+    let range = |k: u128| {
+        let start = first + k * chunk;
+        (start, start.saturating_add(chunk - 1).min(last))
+    };
+    let threads = (threads.max(1) as u128).min(chunk_cnt) as usize;
+    if threads == 1 {
+        for k in 0..chunk_cnt {
+            let (first, last) = range(k);
+            consume(f(first, last))?;
+        }
+        return Ok(());
+    }
+
+    let (f, range) = (&f, &range);
+    std::thread::scope(|s| {
+        let receivers: Vec<_> = (0..threads)
+            .map(|w| {
+                let (sender, receiver) = std::sync::mpsc::sync_channel(1);
+                s.spawn(move || {
+                    let mut k = w as u128;
+                    while k < chunk_cnt {
+                        let (first, last) = range(k);
+                        // the receiver is gone once consume has failed
+                        if sender.send(f(first, last)).is_err() {
+                            return;
+                        }
+                        k += threads as u128;
+                    }
+                });
+                receiver
+            })
+            .collect();
+        for k in 0..chunk_cnt {
+            consume(receivers[(k % threads as u128) as usize].recv().unwrap())?;
+        }
+        Ok(())
+    })
+}
+
+
+#[cfg(test)]
+mod parallel_tests {
+    use super::*;
+
+    #[test]
+    fn ranges_cover_everything_in_order() {
+        assert_eq!(split(0, 9, 3, 1), vec![(0, 3), (4, 7), (8, 9)]);
+        assert_eq!(split(5, 5, 4, 1), vec![(5, 5)]);
+        assert_eq!(split(0, 99, 8, 50), vec![(0, 49), (50, 99)]);
+        assert_eq!(split(0, u128::MAX, 2, 1), vec![(0, u128::MAX / 2), (u128::MAX / 2 + 1, u128::MAX)]);
+    }
+
+    #[test]
+    fn results_are_in_order() {
+        let ranges = split(0, 999, 4, 1);
+        assert_eq!(map(&ranges, |first, last| (first..=last).sum::<u128>()).iter().sum::<u128>(), 999 * 1000 / 2);
+        assert_eq!(map(&ranges, |first, _| first), vec![0, 250, 500, 750]);
+    }
+
+    #[test]
+    fn chunks_are_consumed_in_order() {
+        for threads in [1, 3, 8] {
+            let mut chunks = vec![];
+            let result: Result<(), ()> = for_each_chunk(5, 104, 7, threads, |first, last| (first, last), |x| {
+                chunks.push(x);
+                Ok(())
+            });
+            assert_eq!(result, Ok(()));
+            assert_eq!(chunks.len(), 15);
+            assert_eq!(chunks[0], (5, 11));
+            assert_eq!(chunks[14], (103, 104));
+            assert!(chunks.windows(2).all(|pair| pair[0].1 + 1 == pair[1].0));
+        }
+    }
+
+    #[test]
+    fn failing_consume_stops() {
+        let mut seen = 0;
+        let result = for_each_chunk(0, u128::MAX, 1 << 12, 4, |first, _| first, |_| {
+            seen += 1;
+            if seen == 10 { Err("full") } else { Ok(()) }
+        });
+        assert_eq!((result, seen), (Err("full"), 10));
+    }
+}
@@ -0,0 +1,224 @@
+use crate::formula::{Formula, SecondaryFuncName};
+
+// a law of boolean algebra used to rewrite a formula into an equivalent one.
+// the simplifier only uses the laws up to VacuousQuantification, which make a formula smaller.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Law {
+    Identity,               // P ∧ ⊤ = P, P ∨ ⊥ = P, ⊤ → P = P, P ↔ ⊤ = P
+    Annihilation,           // P ∧ ⊥ = ⊥, P ∨ ⊤ = ⊤, ⊥ → P = ⊤, P → ⊤ = ⊤
+    Idempotence,            // P ∧ P = P, P ∨ P = P
+    Absorption,             // P ∧ (P ∨ Q) = P, P ∨ (P ∧ Q) = P
+    DoubleNegation,         // ¬ ¬ P = P
+    Complement,             // P ∧ ¬ P = ⊥, P ∨ ¬ P = ⊤, ¬ ⊤ = ⊥, ¬ ⊥ = ⊤
+    Negation,               // P → ⊥ = ¬ P, P ↔ ⊥ = ¬ P
+    VacuousQuantification,  // ∀P. Q = Q, ∃P. Q = Q
+    ImplicationElimination, // P → Q = ¬ P ∨ Q
+    EquivalenceElimination, // P ↔ Q = (P → Q) ∧ (Q → P)
+    DeMorgan,               // ¬ (P ∧ Q) = ¬ P ∨ ¬ Q, ¬ (P ∨ Q) = ¬ P ∧ ¬ Q
+    QuantifierNegation,     // ¬ ∀P. Q = ∃P. ¬ Q, ¬ ∃P. Q = ∀P. ¬ Q
+    QuantifierExpansion,    // ∀P. Q = Q[P:=⊤] ∧ Q[P:=⊥], ∃P. Q = Q[P:=⊤] ∨ Q[P:=⊥]
+    Distribution,           // P ∨ (Q ∧ R) = (P ∨ Q) ∧ (P ∨ R)
+}
+
+impl Law {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Identity               => "identity",
+            Self::Annihilation           => "annihilation",
+            Self::Idempotence            => "idempotence",
+            Self::Absorption             => "absorption",
+            Self::DoubleNegation         => "double negation",
+            Self::Complement             => "complement",
+            Self::Negation               => "negation",
+            Self::VacuousQuantification  => "vacuous quantification",
+            Self::ImplicationElimination => "implication elimination",
+            Self::EquivalenceElimination => "equivalence elimination",
+            Self::DeMorgan               => "De Morgan",
+            Self::QuantifierNegation     => "quantifier negation",
+            Self::QuantifierExpansion    => "quantifier expansion",
+            Self::Distribution           => "distribution",
+        }
+    }
+}
+
+impl std::fmt::Display for Law {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+// apply one simplification law at the root of f, if any applies
+pub fn rewrite_root(f: &Formula) -> Option<(Law, Formula)> {
+    match f {
+        Formula::Negation(sub) => {
+            match sub.as_ref() {
+                Formula::Negation(x) => Some((Law::DoubleNegation, (**x).clone())),
+                Formula::True => Some((Law::Complement, Formula::False)),
+                Formula::False => Some((Law::Complement, Formula::True)),
+                _ => None,
+            }
+        },
+        Formula::SecondaryFunc { name, lhs, rhs } => rewrite_secondary_func(*name, lhs, rhs),
+        Formula::Forall(v, body) | Formula::Exists(v, body) if ! body.free_letters().contains(v) => {
+            Some((Law::VacuousQuantification, (**body).clone()))
+        },
+        _ => None,
+    }
+}
+
+fn rewrite_secondary_func(name: SecondaryFuncName, lhs: &Formula, rhs: &Formula) -> Option<(Law, Formula)> {
+    match name {
+        SecondaryFuncName::Conjunction | SecondaryFuncName::Disjunction => {
+            let (identity, annihilator, dual) =
+                if name == SecondaryFuncName::Conjunction {
+                    (Formula::True, Formula::False, SecondaryFuncName::Disjunction)
+                } else {
+                    (Formula::False, Formula::True, SecondaryFuncName::Conjunction)
+                };
+            if *lhs == identity {
+                Some((Law::Identity, rhs.clone()))
+            } else if *rhs == identity {
+                Some((Law::Identity, lhs.clone()))
+            } else if *lhs == annihilator || *rhs == annihilator {
+                Some((Law::Annihilation, annihilator))
+            } else if lhs == rhs {
+                Some((Law::Idempotence, lhs.clone()))
+            } else if is_complement(lhs, rhs) {
+                Some((Law::Complement, annihilator))
+            } else if absorbs(lhs, rhs, dual) {
+                Some((Law::Absorption, lhs.clone()))
+            } else if absorbs(rhs, lhs, dual) {
+                Some((Law::Absorption, rhs.clone()))
+            } else {
+                None
+            }
+        },
+        SecondaryFuncName::Implicature => {
+            match (lhs, rhs) {
+                (Formula::True, _) => Some((Law::Identity, rhs.clone())),
+                (Formula::False, _) | (_, Formula::True) => Some((Law::Annihilation, Formula::True)),
+                (_, Formula::False) => Some((Law::Negation, Formula::Negation(Box::new(lhs.clone())))),
+                _ => None,
+            }
+        },
+        SecondaryFuncName::Equivalence => {
+            match (lhs, rhs) {
+                (Formula::True, x) | (x, Formula::True) => Some((Law::Identity, x.clone())),
+                (Formula::False, x) | (x, Formula::False) => Some((Law::Negation, Formula::Negation(Box::new(x.clone())))),
+                _ => None,
+            }
+        },
+    }
+}
+
+// P and ¬ P in either order
+fn is_complement(a: &Formula, b: &Formula) -> bool {
+    matches!(a, Formula::Negation(x) if **x == *b) || matches!(b, Formula::Negation(x) if **x == *a)
+}
+
+// q is P ∘ R or R ∘ P for the function ∘ named dual
+fn absorbs(p: &Formula, q: &Formula, dual: SecondaryFuncName) -> bool {
+    match q {
+        Formula::SecondaryFunc { name, lhs, rhs } => *name == dual && (**lhs == *p || **rhs == *p),
+        _ => false,
+    }
+}
+
+impl Formula {
+    // rewrite with the simplification laws until none applies anywhere
+    pub fn simplify(&self) -> Formula {
+        let mut f = self.simplify_pass();
+        loop {
+            let next = f.simplify_pass();
+            if next == f {
+                return f;
+            }
+            f = next;
+        }
+    }
+
+    // simplify the children first, then rewrite at the root as long as possible
+    fn simplify_pass(&self) -> Formula {
+        let mut f =
+            match self {
+                Self::Letter(..) | Self::True | Self::False => self.clone(),
+                Self::Negation(sub) => Self::Negation(Box::new(sub.simplify_pass())),
+                Self::SecondaryFunc { name, lhs, rhs } => Self::SecondaryFunc {
+                    name: *name,
+                    lhs: Box::new(lhs.simplify_pass()),
+                    rhs: Box::new(rhs.simplify_pass()),
+                },
+                Self::Forall(v, body) => Self::Forall(*v, Box::new(body.simplify_pass())),
+                Self::Exists(v, body) => Self::Exists(*v, Box::new(body.simplify_pass())),
+            };
+        while let Some((_law, g)) = rewrite_root(&f) {
+            f = g;
+        }
+        f
+    }
+}
+
+
+#[cfg(test)]
+mod simplify_tests {
+    use crate::test_util::formula;
+
+    // the simplified formula must be the expected one, equivalent to the input and no larger
+    fn assert_simplifies(input: &str, expected: &str) {
+        let f = formula(input);
+        let simplified = f.simplify();
+        assert_eq!(simplified, formula(expected), "simplifying {}", f);
+        assert!(simplified.is_equivalent(&f), "{} is not equivalent to {}", simplified, f);
+        assert!(simplified.size() <= f.size());
+    }
+
+    #[test]
+    fn constants() {
+        assert_simplifies("P & t", "P");
+        assert_simplifies("f | P", "P");
+        assert_simplifies("P & f", "f");
+        assert_simplifies("t -> P", "P");
+        assert_simplifies("P -> f", "! P");
+        assert_simplifies("f = P", "! P");
+        assert_simplifies("! t", "f");
+    }
+
+    #[test]
+    fn idempotence_and_absorption() {
+        assert_simplifies("P | P", "P");
+        assert_simplifies("P & ( Q | P )", "P");
+        assert_simplifies("( P & Q ) | P", "P");
+    }
+
+    #[test]
+    fn negations_and_complements() {
+        assert_simplifies("! ! ! P", "! P");
+        assert_simplifies("( P -> Q ) & ! ( P -> Q )", "f");
+        assert_simplifies("! P | P", "t");
+    }
+
+    #[test]
+    fn nested_rewrites_reach_a_fixpoint() {
+        assert_simplifies("( ( P & t ) | f ) & ! ! P", "P");
+        assert_simplifies("∀P. Q & ( P | ! P )", "Q");
+        assert_simplifies("( P -> f ) = f", "P");
+    }
+
+    #[test]
+    fn simplified_formulas_are_equivalent() {
+        let inputs = [
+            "( ( P & Q ) | ( ! P & Q ) ) | R",
+            "( P -> ( Q = t ) ) & ( f | ! ! R )",
+            "! ( P & ! P ) -> ( Q | ( Q & R ) )",
+            "( P = Q ) & ( ( ( P = Q ) | R ) & t )",
+            "∃P. ( P & Q ) | ( ∀R. R | ! R )",
+        ];
+        for input in inputs {
+            let f = formula(input);
+            let simplified = f.simplify();
+            assert!(simplified.is_equivalent(&f), "{} is not equivalent to {}", simplified, f);
+            assert!(simplified.size() <= f.size());
+            assert_eq!(simplified.simplify(), simplified);
+        }
+    }
+}
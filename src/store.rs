@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+use crate::formula::{Assignment, Bound, Formula, SecondaryFuncName, UnboundVariable, Var};
+
+// a handle to a formula in a FormulaStore.
+// a store keeps one copy of each distinct formula, so two handles from the same store
+// are equal exactly when their formulas are.
+#[derive(PartialEq, Eq, Hash, PartialOrd, Ord, Debug, Clone, Copy)]
+pub struct FormulaId(u32);
+
+// a formula node whose subformulas are handles into the same store
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub enum Node {
+    Letter(Var),
+    True,
+    False,
+    Negation(FormulaId),
+    SecondaryFunc {
+        name: SecondaryFuncName,
+        lhs: FormulaId,
+        rhs: FormulaId,
+    },
+    Forall(Var, FormulaId),
+    Exists(Var, FormulaId),
+}
+
+// formulas stored as a DAG: every distinct subformula is stored once and shared by all the
+// formulas containing it, so large generated formulas with repeated subterms stay small
+#[derive(Default, Debug, Clone)]
+pub struct FormulaStore {
+    nodes: Vec<Node>,
+    ids: HashMap<Node, FormulaId>,
+}
+
+impl FormulaStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // the number of distinct formulas stored
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    // the handle of the node, added if it is not in the store yet
+    pub fn intern(&mut self, node: Node) -> FormulaId {
+        if let Some(id) = self.ids.get(&node) {
+            return *id;
+        }
+        let id = FormulaId(u32::try_from(self.nodes.len()).expect("too many formulas in the store"));
+        self.nodes.push(node);
+        self.ids.insert(node, id);
+        id
+    }
+
+    // panics if id is from another store
+    pub fn node(&self, id: FormulaId) -> Node {
+        self.nodes[id.0 as usize]
+    }
+
+    pub fn add(&mut self, formula: &Formula) -> FormulaId {
+        let node =
+            match formula {
+                Formula::Letter(c) => Node::Letter(*c),
+                Formula::True => Node::True,
+                Formula::False => Node::False,
+                Formula::Negation(sub) => Node::Negation(self.add(sub)),
+                Formula::SecondaryFunc { name, lhs, rhs } => {
+                    let lhs = self.add(lhs);
+                    let rhs = self.add(rhs);
+                    Node::SecondaryFunc { name: *name, lhs, rhs }
+                },
+                Formula::Forall(v, body) => Node::Forall(*v, self.add(body)),
+                Formula::Exists(v, body) => Node::Exists(*v, self.add(body)),
+            };
+        self.intern(node)
+    }
+
+    // the formula as a tree, in which shared subformulas are copied
+    pub fn to_formula(&self, id: FormulaId) -> Formula {
+        match self.node(id) {
+            Node::Letter(c) => Formula::Letter(c),
+            Node::True => Formula::True,
+            Node::False => Formula::False,
+            Node::Negation(sub) => Formula::Negation(Box::new(self.to_formula(sub))),
+            Node::SecondaryFunc { name, lhs, rhs } =>
+                Formula::SecondaryFunc { name, lhs: Box::new(self.to_formula(lhs)), rhs: Box::new(self.to_formula(rhs)) },
+            Node::Forall(v, body) => Formula::Forall(v, Box::new(self.to_formula(body))),
+            Node::Exists(v, body) => Formula::Exists(v, Box::new(self.to_formula(body))),
+        }
+    }
+
+    // the truth value of the formula, as Formula::eval, evaluating each shared subformula once.
+    // quantified letters are bound on the call stack as in Formula::eval, so the store is left as it is.
+    pub fn eval(&self, id: FormulaId, assignment: &(impl Assignment + ?Sized)) -> Result<bool, UnboundVariable> {
+        self.eval_memo(id, assignment, None, &mut HashMap::new())
+    }
+
+    // memo holds the truth values under the current bindings, so each quantifier instance gets its own
+    fn eval_memo(
+        &self,
+        id: FormulaId,
+        assignment: &(impl Assignment + ?Sized),
+        bound: Option<&Bound>,
+        memo: &mut HashMap<FormulaId, bool>,
+    ) -> Result<bool, UnboundVariable> {
+        if let Some(b) = memo.get(&id) {
+            return Ok(*b);
+        }
+        let value =
+            match self.node(id) {
+                Node::Letter(c) => Bound::get(bound, c).or_else(|| assignment.get(c)).ok_or(UnboundVariable(c))?,
+                Node::True => true,
+                Node::False => false,
+                Node::Negation(sub) => ! self.eval_memo(sub, assignment, bound, memo)?,
+                Node::SecondaryFunc { name, lhs, rhs } => {
+                    let lhs = self.eval_memo(lhs, assignment, bound, memo)?;
+                    let rhs = self.eval_memo(rhs, assignment, bound, memo)?;
+                    match name {
+                        SecondaryFuncName::Conjunction => lhs && rhs,
+                        SecondaryFuncName::Disjunction => lhs || rhs,
+                        SecondaryFuncName::Implicature => ! lhs || rhs,
+                        SecondaryFuncName::Equivalence => lhs == rhs,
+                    }
+                },
+                Node::Forall(v, body) | Node::Exists(v, body) => {
+                    let if_true = self.eval_memo(body, assignment, Some(&Bound { var: v, value: true, outer: bound }), &mut HashMap::new())?;
+                    let if_false = self.eval_memo(body, assignment, Some(&Bound { var: v, value: false, outer: bound }), &mut HashMap::new())?;
+                    if matches!(self.node(id), Node::Forall(..)) { if_true && if_false } else { if_true || if_false }
+                },
+            };
+        memo.insert(id, value);
+        Ok(value)
+    }
+}
+
+
+#[cfg(test)]
+mod store_tests {
+    use super::*;
+    use crate::test_util::formula;
+
+    #[test]
+    fn sharing() {
+        let mut store = FormulaStore::new();
+        let f = formula("( P & Q ) | ( P & Q )");
+        let id = store.add(&f);
+        // P, Q, P ∧ Q and the disjunction
+        assert_eq!(store.len(), 4);
+        assert_eq!(store.to_formula(id), f);
+        assert_eq!(store.add(&formula("( P & Q ) | ( P & Q )")), id);
+        assert_ne!(store.add(&formula("( Q & P ) | ( P & Q )")), id);
+        match store.node(id) {
+            Node::SecondaryFunc { lhs, rhs, .. } => assert_eq!(lhs, rhs),
+            _ => panic!("not a disjunction"),
+        }
+    }
+
+    #[test]
+    fn agrees_with_eval() {
+        let mut store = FormulaStore::new();
+        for s in ["( P -> Q ) & ! R", "( P = Q ) | ( ∀P. P -> Q )", "∃Q. ( P & Q ) | ∀R. R = Q", "∀P. ∃P. P"] {
+            let f = formula(s);
+            let id = store.add(&f);
+            let letters: Vec<_> = f.free_letters().into_iter().collect();
+            for (values, _) in f.rows() {
+                let interpretation: HashMap<_, _> = letters.iter().copied().zip(values).collect();
+                assert_eq!(store.eval(id, &interpretation), f.eval(&interpretation), "{}", s);
+            }
+        }
+        // evaluating quantifiers adds nothing to the store
+        let id = store.add(&formula("∀P. ∃Q. P = Q"));
+        let len = store.len();
+        assert_eq!(store.eval(id, &[]), Ok(true));
+        assert_eq!(store.len(), len);
+        let id = store.add(&formula("P & Q"));
+        assert_eq!(store.eval(id, &[('P', true)]), Err(UnboundVariable('Q')));
+    }
+
+    #[test]
+    fn deep_sharing() {
+        // 2^100 nodes as a tree, 101 in the store
+        let mut store = FormulaStore::new();
+        let mut id = store.intern(Node::Letter('P'));
+        for _ in 0..100 {
+            id = store.intern(Node::SecondaryFunc { name: SecondaryFuncName::Equivalence, lhs: id, rhs: id });
+        }
+        assert_eq!(store.len(), 101);
+        assert_eq!(store.eval(id, &[('P', false)]), Ok(true));
+    }
+}
@@ -0,0 +1,632 @@
+use std::collections::{HashMap, BTreeSet};
+use unicode_width::UnicodeWidthStr;
+use crate::compile::Compiled;
+use crate::formula::{Formula, TableOptions, Var};
+
+// a truth table with one column per chosen formula, usually subformulas of one formula
+pub struct TruthTable {
+    pub letters: Vec<Var>,
+    pub columns: Vec<Formula>,
+    pub rows: Vec<Row>,
+}
+
+// the truth values of the letters and of each column under one interpretation
+pub struct Row {
+    pub interpretation: Vec<bool>,
+    pub values: Vec<bool>,
+}
+
+// the order rows are listed in
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum RowOrder {
+    #[default]
+    FalseFirst, // counting up in binary from all false
+    TrueFirst,  // counting down from all true, as most textbooks do
+    Gray,       // from all false, changing a single letter from one row to the next
+}
+
+impl RowOrder {
+    // the truth values of letter_cnt letters in the i-th row, the first letter being the most significant bit
+    pub fn interpretation(&self, i: u128, letter_cnt: usize) -> Vec<bool> {
+        let bits = self.index(i, letter_cnt);
+        (0..letter_cnt).map(|j| bits & 1<<(letter_cnt - j - 1) != 0).collect()
+    }
+
+    // the position of the i-th row when counting from all false
+    pub fn index(&self, i: u128, letter_cnt: usize) -> u128 {
+        let bits =
+            match self {
+                Self::FalseFirst => i,
+                Self::TrueFirst => ! i,
+                Self::Gray => i ^ (i >> 1),
+            };
+        bits & Interpretations::last_row(letter_cnt)
+    }
+}
+
+// the interpretations of letter_cnt letters in a row order, one at a time.
+// the row counter is a u128, so tables over up to 128 letters can be started, if not finished.
+pub struct Interpretations {
+    order: RowOrder,
+    letter_cnt: usize,
+    next: Option<u128>,
+    last: u128,
+}
+
+// the value of a compiled formula row by row, evaluating each block of 64 rows once
+// as long as the rows come a block at a time, which they do in every RowOrder
+pub(crate) struct BlockCache {
+    compiled: Compiled,
+    block: Option<(u128, u64)>,
+}
+
+impl BlockCache {
+    pub(crate) fn new(compiled: Compiled) -> Self {
+        Self { compiled, block: None }
+    }
+
+    pub(crate) fn value(&mut self, index: u128) -> bool {
+        let block = index >> 6;
+        let values =
+            match self.block {
+                Some((b, values)) if b == block => values,
+                _ => {
+                    let values = self.compiled.evaluate_block(block);
+                    self.block = Some((block, values));
+                    values
+                },
+            };
+        values >> (index & 63) & 1 == 1
+    }
+}
+
+impl Interpretations {
+    pub fn new(letter_cnt: usize, order: RowOrder) -> Self {
+        Self::between(letter_cnt, order, 0, Self::last_row(letter_cnt))
+    }
+
+    // only the rows from first to last, both included
+    pub fn between(letter_cnt: usize, order: RowOrder, first: u128, last: u128) -> Self {
+        assert!(letter_cnt <= 128, "a truth table can have at most 128 letters");
+        assert!(first <= last && last <= Self::last_row(letter_cnt));
+        Self { order, letter_cnt, next: Some(first), last }
+    }
+
+    // the number of the last row of a table over letter_cnt letters, counting from 0
+    pub fn last_row(letter_cnt: usize) -> u128 {
+        u128::MAX.checked_shr(128 - letter_cnt as u32).unwrap_or(0)
+    }
+
+    // the number of interpretations left
+    pub fn remaining(&self) -> Option<u128> {
+        self.next.map(|i| self.last - i).map_or(Some(0), |x| x.checked_add(1))
+    }
+
+    // the position the next interpretation has when counting from all false
+    pub fn next_index(&self) -> Option<u128> {
+        self.next.map(|i| self.order.index(i, self.letter_cnt))
+    }
+}
+
+impl Iterator for Interpretations {
+    type Item = Vec<bool>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let i = self.next?;
+        self.next = if i == self.last { None } else { Some(i + 1) };
+        Some(self.order.interpretation(i, self.letter_cnt))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.remaining().map(usize::try_from) {
+            Some(Ok(n)) => (n, Some(n)),
+            _ => (usize::MAX, None),
+        }
+    }
+}
+
+// the rows of the truth table of a formula, evaluated lazily.
+// each item is the truth values of the letters, in the order of Rows::letters, and the value of the formula.
+pub struct Rows {
+    interpretations: Interpretations,
+    values: BlockCache,
+}
+
+impl Rows {
+    pub fn new(formula: &Formula, order: RowOrder) -> Self {
+        let values = BlockCache::new(Compiled::new(formula));
+        let interpretations = Interpretations::new(values.compiled.letters().len(), order);
+        Self { interpretations, values }
+    }
+
+    pub fn letters(&self) -> &[Var] {
+        self.values.compiled.letters()
+    }
+}
+
+impl Iterator for Rows {
+    type Item = (Vec<bool>, bool);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.interpretations.next_index()?;
+        let interpretation = self.interpretations.next()?;
+        Some((interpretation, self.values.value(index)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.interpretations.size_hint()
+    }
+}
+
+impl std::convert::TryFrom<&str> for RowOrder {
+    type Error = &'static str;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "false-first" => Ok( Self::FalseFirst ),
+            "true-first" => Ok( Self::TrueFirst ),
+            "gray" => Ok( Self::Gray ),
+            _ => Err("Not a row order")
+        }
+    }
+}
+
+// the text truth values are written with
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Labels {
+    pub true_label: String,
+    pub false_label: String,
+    pub unknown_label: String, // only written by three-valued tables
+}
+
+impl Default for Labels {
+    fn default() -> Self {
+        Self::new("T", "F")
+    }
+}
+
+impl Labels {
+    // unknown is written as U
+    pub fn new(true_label: &str, false_label: &str) -> Self {
+        Self::with_unknown(true_label, false_label, "U")
+    }
+
+    pub fn with_unknown(true_label: &str, false_label: &str, unknown_label: &str) -> Self {
+        Self { true_label: true_label.to_string(), false_label: false_label.to_string(), unknown_label: unknown_label.to_string() }
+    }
+
+    pub fn numeric() -> Self {
+        Self::with_unknown("1", "0", "?")
+    }
+
+    pub fn symbols() -> Self {
+        Self::with_unknown("⊤", "⊥", "?")
+    }
+
+    pub fn get(&self, b: bool) -> &str {
+        if b { &self.true_label } else { &self.false_label }
+    }
+
+    // the display width of the wider label
+    pub fn width(&self) -> usize {
+        self.true_label.width().max(self.false_label.width())
+    }
+}
+
+// "tf", "10", "symbols", or custom labels separated by a comma such as "yes,no" or "yes,no,maybe",
+// the third one being for unknown
+impl std::convert::TryFrom<&str> for Labels {
+    type Error = &'static str;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "tf" => Ok( Self::default() ),
+            "10" => Ok( Self::numeric() ),
+            "symbols" => Ok( Self::symbols() ),
+            x => {
+                let parts: Vec<_> = x.split(',').collect();
+                let distinct = parts.iter().enumerate().all(|(i, a)| ! a.is_empty() && ! parts[..i].contains(a));
+                match parts[..] {
+                    [t, f] if distinct => Ok( Self::new(t, f) ),
+                    [t, f, u] if distinct => Ok( Self::with_unknown(t, f, u) ),
+                    _ => Err("Not truth value labels"),
+                }
+            },
+        }
+    }
+}
+
+// which rows a table shows
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct RowFilter {
+    pub value: Option<bool>,            // only the rows where the formula has this value
+    pub assignment: HashMap<Var, bool>, // only the rows agreeing with this partial assignment
+}
+
+impl RowFilter {
+    // the rows where the formula is true
+    pub fn models() -> Self {
+        Self { value: Some(true), ..Self::default() }
+    }
+
+    // the rows where the formula is false
+    pub fn countermodels() -> Self {
+        Self { value: Some(false), ..Self::default() }
+    }
+
+    pub fn keeps(&self, interpretation: &HashMap<Var, bool>, value: bool) -> bool {
+        self.value.is_none_or(|v| v == value) &&
+            self.assignment.iter().all(|(c, b)| interpretation.get(c).is_none_or(|x| x == b))
+    }
+}
+
+// a partial assignment such as "P=T,Q=F"
+pub fn parse_assignment(s: &str) -> Result<HashMap<Var, bool>, &'static str> {
+    let mut assignment = HashMap::new();
+    for part in s.split(',') {
+        let (c, b) = part.trim().split_once('=').ok_or("Not an assignment")?;
+        let mut chars = c.chars();
+        let c = match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_uppercase() => c,
+            _ => return Err("Not a letter"),
+        };
+        let b = match b {
+            "T" | "1" => true,
+            "F" | "0" => false,
+            _ => return Err("Not a truth value"),
+        };
+        if assignment.insert(c, b).is_some_and(|x| x != b) {
+            return Err("Conflicting assignment");
+        }
+    }
+    Ok(assignment)
+}
+
+impl TruthTable {
+    // rows over the free letters of all columns, from all false to all true
+    pub fn new(columns: Vec<Formula>) -> Self {
+        Self::with_order(columns, RowOrder::default())
+    }
+
+    pub fn with_order(columns: Vec<Formula>, order: RowOrder) -> Self {
+        let mut letters = BTreeSet::new();
+        for column in &columns {
+            letters.append(&mut column.free_letters());
+        }
+        Self::with_letters(letters.into_iter().collect(), columns, order)
+    }
+
+    // rows over the given letters, which must include the free letters of every column
+    fn with_letters(letters: Vec<Var>, columns: Vec<Formula>, order: RowOrder) -> Self {
+        // every column is compiled over all the letters, so that the rows of the columns line up
+        let mut compiled: Vec<_> = columns.iter()
+            .map(|column| BlockCache::new(Compiled::with_letters(column, &letters)))
+            .collect();
+
+        let mut rows = vec![];
+        let mut interpretations = Interpretations::new(letters.len(), order);
+        while let Some(index) = interpretations.next_index() {
+            let interpretation = interpretations.next().unwrap();
+            let values = compiled.iter_mut().map(|column| column.value(index)).collect();
+            rows.push(Row { interpretation, values });
+        }
+
+        Self { letters, columns, rows }
+    }
+
+    // one column per compound subformula, bottom-up, ending with the formula itself
+    pub fn subformulas(formula: &Formula) -> Self {
+        Self::subformulas_with_order(formula, RowOrder::default())
+    }
+
+    // drop the rows the filter does not keep, judging the formula by the last column
+    pub fn retain_rows(&mut self, filter: &RowFilter) {
+        let letters = &self.letters;
+        self.rows.retain(|row| {
+            let interpretation: HashMap<_, _> = letters.iter().copied().zip(row.interpretation.iter().copied()).collect();
+            filter.keeps(&interpretation, row.values.last().copied().unwrap_or(true))
+        });
+    }
+
+    pub fn subformulas_with_order(formula: &Formula, order: RowOrder) -> Self {
+        let mut columns = vec![];
+        compound_subformulas(formula, &BTreeSet::new(), &mut columns);
+        Self::with_order(columns, order)
+    }
+
+    // the columns of subformulas_with_order that were selected, and the formula itself last.
+    // selected letters are shown anyway, so only compound subformulas add a column.
+    pub fn selected_subformulas(formula: &Formula, selected: &[Formula], order: RowOrder) -> Result<Self, &'static str> {
+        let subformulas = formula.subformulas();
+        let mut columns = vec![];
+        compound_subformulas(formula, &BTreeSet::new(), &mut columns);
+        for s in selected {
+            if ! subformulas.contains(&s) {
+                return Err("Not a subformula of the formula");
+            }
+            let is_compound = ! matches!(s, Formula::Letter(..) | Formula::True | Formula::False);
+            if is_compound && ! columns.contains(s) {
+                return Err("The subformula has a bound letter");
+            }
+        }
+        columns.retain(|column| column == formula || selected.contains(column));
+        Ok(Self::with_letters(formula.free_letters().into_iter().collect(), columns, order))
+    }
+}
+
+// collect the distinct compound subformulas in post-order.
+// subformulas mentioning a bound letter have no value of their own under a row, so they are left out.
+fn compound_subformulas(f: &Formula, bound: &BTreeSet<Var>, columns: &mut Vec<Formula>) {
+    match f {
+        Formula::Forall(v, body) | Formula::Exists(v, body) => {
+            let mut bound = bound.clone();
+            bound.insert(*v);
+            compound_subformulas(body, &bound, columns);
+        },
+        _ => {
+            for child in f.children() {
+                compound_subformulas(child, bound, columns);
+            }
+        },
+    }
+
+    let is_compound = ! matches!(f, Formula::Letter(..) | Formula::True | Formula::False);
+    if is_compound && f.free_letters().is_disjoint(bound) && ! columns.contains(f) {
+        columns.push(f.clone());
+    }
+}
+
+impl std::fmt::Display for TruthTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_string_with(&TableOptions::default()))
+    }
+}
+
+impl TruthTable {
+    // each truth value is written under the main connective of its column.
+    // the row order is the one the table was built with, and colors are not supported.
+    pub fn to_string_with(&self, options: &TableOptions) -> String {
+        let labels = &options.labels;
+        let letters: Vec<_> = self.letters.iter().map(|c| c.to_string()).collect();
+        let letter_widths: Vec<_> = letters.iter().map(|c| c.width().max(labels.width())).collect();
+        let headers: Vec<_> = self.columns.iter().map(|column| column.to_string_with(options.symbols)).collect();
+        let offsets: Vec<_> = self.columns.iter().map(|column| column.main_connective_offset(options.symbols)).collect();
+        // a label wider than the main connective may stick out past the formula
+        let widths: Vec<_> = headers.iter().zip(&offsets).map(|(h, offset)| h.width().max(offset + labels.width())).collect();
+
+        let mut header = String::new();
+        for (c, width) in letters.iter().zip(&letter_widths) {
+            header.push_str(c);
+            header.push_str(&" ".repeat(width - c.width() + 1));
+        }
+        header.push('|');
+        for (i, (h, width)) in headers.iter().zip(&widths).enumerate() {
+            if i > 0 {
+                header.push_str(" |");
+            }
+            header.push(' ');
+            header.push_str(h);
+            header.push_str(&" ".repeat(width - h.width()));
+        }
+
+        let mut s = String::from(header.trim_end());
+        s.push('\n');
+
+        for row in &self.rows {
+            let mut line = String::new();
+            for (b, width) in row.interpretation.iter().zip(&letter_widths) {
+                let label = labels.get(*b);
+                line.push_str(label);
+                line.push_str(&" ".repeat(width - label.width() + 1));
+            }
+            line.push('|');
+            for (i, b) in row.values.iter().enumerate() {
+                if i > 0 {
+                    line.push_str(" |");
+                }
+                let label = labels.get(*b);
+                line.push_str(&" ".repeat(offsets[i] + 1));
+                line.push_str(label);
+                line.push_str(&" ".repeat(widths[i] - offsets[i] - label.width()));
+            }
+            s.push_str(line.trim_end());
+            s.push('\n');
+        }
+        s
+    }
+}
+
+
+#[cfg(test)]
+mod table_tests {
+    use super::*;
+    use crate::alphabet::SymbolSet;
+    use crate::test_util::formula;
+
+    #[test]
+    fn columns_are_distinct_compound_subformulas_bottom_up() {
+        let table = TruthTable::subformulas(&formula("( P & Q ) -> ! ( P & Q )"));
+        assert_eq!(
+            table.columns,
+            vec![
+                formula("P & Q"),
+                formula("! ( P & Q )"),
+                formula("( P & Q ) -> ! ( P & Q )"),
+            ]
+        );
+        assert_eq!(table.letters, vec!['P', 'Q']);
+        assert_eq!(table.rows.len(), 4);
+    }
+
+    #[test]
+    fn bound_subformulas_are_left_out() {
+        let table = TruthTable::subformulas(&formula("Q & ( ∀P. P | Q )"));
+        assert_eq!(
+            table.columns,
+            vec![
+                formula("∀P. P | Q"),
+                formula("Q & ( ∀P. P | Q )"),
+            ]
+        );
+    }
+
+    #[test]
+    fn selected_columns() {
+        let table = TruthTable::new(vec![formula("P -> Q"), formula("! Q")]);
+        let values: Vec<_> = table.rows.iter().map(|row| row.values.clone()).collect();
+        assert_eq!(
+            values,
+            vec![
+                vec![true, true],
+                vec![true, false],
+                vec![false, true],
+                vec![true, false],
+            ]
+        );
+    }
+
+    #[test]
+    fn selected_subformulas() {
+        let f = formula("( P & Q ) -> ( ! R | P )");
+        let selected = [formula("P"), formula("! R | P"), formula("P & Q")];
+        let table = TruthTable::selected_subformulas(&f, &selected, RowOrder::default()).unwrap();
+        assert_eq!(table.columns, vec![formula("P & Q"), formula("! R | P"), f.clone()]);
+        assert_eq!(table.letters, vec!['P', 'Q', 'R']);
+        assert_eq!(table.rows.len(), 8);
+
+        // the formula is always the last column, and rows are over all its letters
+        let table = TruthTable::selected_subformulas(&formula("P -> Q"), &[formula("P")], RowOrder::default()).unwrap();
+        assert_eq!(table.columns, vec![formula("P -> Q")]);
+        assert_eq!(table.letters, vec!['P', 'Q']);
+
+        assert!(TruthTable::selected_subformulas(&f, &[formula("Q & P")], RowOrder::default()).is_err());
+        assert!(TruthTable::selected_subformulas(&formula("∀P. P | Q"), &[formula("P | Q")], RowOrder::default()).is_err());
+    }
+
+    #[test]
+    fn ascii_table() {
+        let table = TruthTable::subformulas(&formula("! P -> Q"));
+        assert_eq!(
+            table.to_string_with(&TableOptions { symbols: SymbolSet::Ascii, ..TableOptions::default() }),
+            String::from(
+"P Q | ~ P | ~ P -> Q
+F F | T   |     F
+F T | T   |     T
+T F | F   |     T
+T T | F   |     T
+"
+            )
+        );
+    }
+
+    #[test]
+    fn row_orders() {
+        let rows = |order: RowOrder| (0..4).map(|i| order.interpretation(i, 2)).collect::<Vec<_>>();
+        assert_eq!(rows(RowOrder::FalseFirst), vec![vec![false, false], vec![false, true], vec![true, false], vec![true, true]]);
+        assert_eq!(rows(RowOrder::TrueFirst), vec![vec![true, true], vec![true, false], vec![false, true], vec![false, false]]);
+        assert_eq!(rows(RowOrder::Gray), vec![vec![false, false], vec![false, true], vec![true, true], vec![true, false]]);
+    }
+
+    #[test]
+    fn filter_rows() {
+        let mut table = TruthTable::subformulas(&formula("P -> Q"));
+        table.retain_rows(&RowFilter::countermodels());
+        let interpretations: Vec<_> = table.rows.iter().map(|row| row.interpretation.clone()).collect();
+        assert_eq!(interpretations, vec![vec![true, false]]);
+
+        let mut table = TruthTable::subformulas(&formula("( P & Q ) | R"));
+        table.retain_rows(&RowFilter { value: Some(true), assignment: parse_assignment("P=T, R=0").unwrap() });
+        let interpretations: Vec<_> = table.rows.iter().map(|row| row.interpretation.clone()).collect();
+        assert_eq!(interpretations, vec![vec![true, true, false]]);
+    }
+
+    #[test]
+    fn assignments() {
+        assert_eq!(parse_assignment("P=T,Q=0"), Ok(HashMap::from([('P', true), ('Q', false)])));
+        assert!(parse_assignment("P=T,P=F").is_err());
+        assert!(parse_assignment("p=T").is_err());
+        assert!(parse_assignment("P").is_err());
+    }
+
+    #[test]
+    fn lazy_rows() {
+        let f = formula("P -> Q");
+        let rows = Rows::new(&f, RowOrder::FalseFirst);
+        assert_eq!(rows.letters(), ['P', 'Q']);
+        assert_eq!(rows.size_hint(), (4, Some(4)));
+        let values: Vec<_> = rows.map(|(_, value)| value).collect();
+        assert_eq!(values, vec![true, true, false, true]);
+
+        let f = formula("t");
+        assert_eq!(Rows::new(&f, RowOrder::FalseFirst).collect::<Vec<_>>(), vec![(vec![], true)]);
+    }
+
+    #[test]
+    fn rows_of_a_wide_formula_are_lazy() {
+        let letters: Vec<_> = ('A'..='Z').chain('Α'..='Ω').filter(|c| c.is_uppercase()).take(40).collect();
+        let f = letters[1..].iter().fold(Formula::Letter(letters[0]), |lhs, c| Formula::SecondaryFunc {
+            name: crate::formula::SecondaryFuncName::Disjunction,
+            lhs: Box::new(lhs),
+            rhs: Box::new(Formula::Letter(*c)),
+        });
+        let mut rows = f.rows();
+        assert_eq!(rows.size_hint(), (1 << 40, Some(1 << 40)));
+        assert_eq!(rows.next(), Some((vec![false; 40], false)));
+        assert_eq!(rows.next().map(|(_, value)| value), Some(true));
+    }
+
+    #[test]
+    fn many_letters() {
+        assert_eq!(Interpretations::between(3, RowOrder::Gray, 2, 3).collect::<Vec<_>>(), vec![vec![false, true, true], vec![false, true, false]]);
+        let mut interpretations = Interpretations::new(128, RowOrder::TrueFirst);
+        assert_eq!(interpretations.remaining(), None);
+        assert_eq!(interpretations.next(), Some(vec![true; 128]));
+        let mut last = vec![true; 128];
+        last[127] = false;
+        assert_eq!(interpretations.next(), Some(last));
+        assert_eq!(Interpretations::new(100, RowOrder::FalseFirst).remaining(), Some(1 << 100));
+    }
+
+    #[test]
+    fn labels() {
+        assert_eq!(Labels::try_from("10"), Ok(Labels::numeric()));
+        assert_eq!(Labels::try_from("yes,no"), Ok(Labels::new("yes", "no")));
+        assert_eq!(Labels::try_from("yes,no,maybe"), Ok(Labels::with_unknown("yes", "no", "maybe")));
+        assert!(Labels::try_from("yes").is_err());
+        assert!(Labels::try_from(",no").is_err());
+        assert!(Labels::try_from("yes,no,yes").is_err());
+        assert!(Labels::try_from("yes,no,").is_err());
+        assert!(Labels::try_from("a,b,c,d").is_err());
+    }
+
+    #[test]
+    fn wide_labels_and_true_first() {
+        let table = TruthTable::subformulas_with_order(&formula("! P"), RowOrder::TrueFirst);
+        let options = TableOptions { labels: Labels::new("yes", "no"), ..TableOptions::default() };
+        assert_eq!(
+            table.to_string_with(&options),
+            String::from(
+"P   | ¬ P
+yes | no
+no  | yes
+"
+            )
+        );
+    }
+
+    #[test]
+    fn fmt_table() {
+        let table = TruthTable::subformulas(&formula("! P | Q"));
+        assert_eq!(
+            table.to_string(),
+            String::from(
+"P Q | ¬ P | ¬ P ∨ Q
+F F | T   |     T
+F T | T   |     T
+T F | F   |     F
+T T | F   |     T
+"
+            )
+        );
+    }
+}
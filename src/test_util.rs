@@ -0,0 +1,11 @@
+use crate::alphabet::Alphabet;
+use crate::formula::Formula;
+
+// the symbols of s, separated by spaces as typed at the prompt
+pub(crate) fn sentence(s: &str) -> Vec<Alphabet> {
+    Alphabet::sentence(s).unwrap()
+}
+
+pub(crate) fn formula(s: &str) -> Formula {
+    Formula::parse(&sentence(s)).unwrap()
+}
@@ -0,0 +1,428 @@
+use std::collections::HashMap;
+use std::io::Write;
+use unicode_width::UnicodeWidthStr;
+use crate::alphabet::{Alphabet, SecondaryFuncName};
+use crate::formula::{cell, column_width, Formula, TableOptions, UnboundVariable, Var};
+use crate::table::{Labels, RowOrder};
+
+// a truth value of a three-valued logic, ordered from false to true
+#[derive(PartialEq, Eq, Hash, PartialOrd, Ord, Debug, Clone, Copy)]
+pub enum TruthValue {
+    False,
+    Unknown, // such as the reading of a sensor which did not report
+    True,
+}
+
+impl TruthValue {
+    // the order quantified letters run through by default, see Semantics::values
+    pub const ALL: [TruthValue; 3] = [TruthValue::True, TruthValue::Unknown, TruthValue::False];
+
+    // 0, 1 or 2 from false to true, as in the definition of Łukasiewicz logic
+    fn rank(self) -> u8 {
+        self as u8
+    }
+
+    fn from_rank(rank: u8) -> Self {
+        match rank {
+            0 => Self::False,
+            1 => Self::Unknown,
+            _ => Self::True,
+        }
+    }
+
+    pub fn label(self, labels: &Labels) -> &str {
+        match self {
+            Self::True => labels.get(true),
+            Self::False => labels.get(false),
+            Self::Unknown => &labels.unknown_label,
+        }
+    }
+
+    // the truth value if it is known
+    pub fn known(self) -> Option<bool> {
+        match self {
+            Self::True => Some(true),
+            Self::False => Some(false),
+            Self::Unknown => None,
+        }
+    }
+}
+
+impl From<bool> for TruthValue {
+    fn from(b: bool) -> Self {
+        if b { Self::True } else { Self::False }
+    }
+}
+
+impl std::fmt::Display for TruthValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.label(&Labels::default()))
+    }
+}
+
+// the truth tables of the connectives in a three-valued logic.
+// ∀ and ∃ are the conjunction and the disjunction of the instances.
+pub trait Semantics {
+    // the truth values a quantified letter runs through
+    fn values(&self) -> &'static [TruthValue] {
+        &TruthValue::ALL
+    }
+
+    fn negation(&self, a: TruthValue) -> TruthValue;
+
+    fn secondary_func(&self, name: SecondaryFuncName, lhs: TruthValue, rhs: TruthValue) -> TruthValue;
+}
+
+// two-valued logic, for comparing the three-valued logics with.
+// quantified letters are only true or false, and a connective applied to unknown is unknown.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct Classical;
+
+impl Semantics for Classical {
+    fn values(&self) -> &'static [TruthValue] {
+        &[TruthValue::True, TruthValue::False]
+    }
+
+    fn negation(&self, a: TruthValue) -> TruthValue {
+        a.known().map_or(TruthValue::Unknown, |a| TruthValue::from(! a))
+    }
+
+    fn secondary_func(&self, name: SecondaryFuncName, lhs: TruthValue, rhs: TruthValue) -> TruthValue {
+        let (Some(a), Some(b)) = (lhs.known(), rhs.known()) else {
+            return TruthValue::Unknown;
+        };
+        TruthValue::from(
+            match name {
+                SecondaryFuncName::Conjunction => a && b,
+                SecondaryFuncName::Disjunction => a || b,
+                SecondaryFuncName::Implicature => ! a || b,
+                SecondaryFuncName::Equivalence => a == b,
+            }
+        )
+    }
+}
+
+// the three-valued logics this crate knows, all of which agree with classical logic on true and false
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum Logic {
+    #[default]
+    Kleene,      // strong Kleene: unknown unless the known values decide, as F ∧ U = F
+    Bochvar,     // weak Kleene: unknown as soon as anything is unknown, as F ∧ U = U
+    Lukasiewicz, // strong Kleene, except U → U and U ↔ U are true
+}
+
+impl Semantics for Logic {
+    fn negation(&self, a: TruthValue) -> TruthValue {
+        TruthValue::from_rank(2 - a.rank())
+    }
+
+    fn secondary_func(&self, name: SecondaryFuncName, lhs: TruthValue, rhs: TruthValue) -> TruthValue {
+        if *self == Self::Bochvar && (lhs == TruthValue::Unknown || rhs == TruthValue::Unknown) {
+            return TruthValue::Unknown;
+        }
+        let (a, b) = (lhs.rank(), rhs.rank());
+        let rank =
+            match (self, name) {
+                (_, SecondaryFuncName::Conjunction) => a.min(b),
+                (_, SecondaryFuncName::Disjunction) => a.max(b),
+                (Self::Lukasiewicz, SecondaryFuncName::Implicature) => (2 - a + b).min(2),
+                (Self::Lukasiewicz, SecondaryFuncName::Equivalence) => 2 - a.abs_diff(b),
+                (_, SecondaryFuncName::Implicature) => (2 - a).max(b),
+                (_, SecondaryFuncName::Equivalence) => (2 - a).max(b).min((2 - b).max(a)),
+            };
+        TruthValue::from_rank(rank)
+    }
+}
+
+impl std::convert::TryFrom<&str> for Logic {
+    type Error = &'static str;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "kleene" => Ok( Self::Kleene ),
+            "bochvar" => Ok( Self::Bochvar ),
+            "lukasiewicz" => Ok( Self::Lukasiewicz ),
+            _ => Err("Not a three-valued logic")
+        }
+    }
+}
+
+// the truth values of letter_cnt letters in the i-th of the 3^letter_cnt rows, the first letter being
+// the most significant digit. the orders are those of RowOrder, counting in base 3 from F through U to T.
+fn interpretation(order: RowOrder, i: u128, letter_cnt: usize) -> Vec<TruthValue> {
+    let mut digits = vec![0; letter_cnt];
+    let mut rest = i;
+    for digit in digits.iter_mut().rev() {
+        *digit = (rest % 3) as u8;
+        rest /= 3;
+    }
+    // the reflected ternary Gray code runs a digit backwards when the digits before it add up to an odd number
+    let mut sum = 0;
+    digits.into_iter()
+        .map(|digit| {
+            let rank =
+                match order {
+                    RowOrder::FalseFirst => digit,
+                    RowOrder::TrueFirst => 2 - digit,
+                    RowOrder::Gray => if sum % 2 == 0 { digit } else { 2 - digit },
+                };
+            sum += digit as u32;
+            TruthValue::from_rank(rank)
+        })
+        .collect()
+}
+
+impl Formula {
+    // the truth value under a three-valued semantics.
+    // a quantified letter runs through all three truth values.
+    pub fn eval_three_valued(
+        &self,
+        semantics: &(impl Semantics + ?Sized),
+        interpretation: &HashMap<Var, TruthValue>,
+    ) -> Result<TruthValue, UnboundVariable> {
+        self.node_values(semantics, interpretation, &mut vec![])
+    }
+
+    // the truth value, after pushing the truth value of every node in the order their symbols are written.
+    // the instance of a quantifier body shown is the first one with the truth value of the quantifier.
+    fn node_values(
+        &self,
+        semantics: &(impl Semantics + ?Sized),
+        interpretation: &HashMap<Var, TruthValue>,
+        values: &mut Vec<TruthValue>,
+    ) -> Result<TruthValue, UnboundVariable> {
+        match self {
+            Self::Letter(c) => {
+                let value = *interpretation.get(c).ok_or(UnboundVariable(*c))?;
+                values.push(value);
+                Ok(value)
+            },
+            Self::True | Self::False => {
+                let value = TruthValue::from(*self == Self::True);
+                values.push(value);
+                Ok(value)
+            },
+            Self::Negation(sub) => {
+                let at = values.len();
+                values.push(TruthValue::Unknown);
+                let value = semantics.negation(sub.node_values(semantics, interpretation, values)?);
+                values[at] = value;
+                Ok(value)
+            },
+            Self::SecondaryFunc { name, lhs, rhs } => {
+                let lhs = lhs.node_values(semantics, interpretation, values)?;
+                let at = values.len();
+                values.push(TruthValue::Unknown);
+                let rhs = rhs.node_values(semantics, interpretation, values)?;
+                let value = semantics.secondary_func(*name, lhs, rhs);
+                values[at] = value;
+                Ok(value)
+            },
+            Self::Forall(v, body) | Self::Exists(v, body) => {
+                let name = if matches!(self, Self::Forall(..)) { SecondaryFuncName::Conjunction } else { SecondaryFuncName::Disjunction };
+                let mut interpretation = interpretation.clone();
+                let mut instances = vec![];
+                for x in semantics.values().iter().copied() {
+                    interpretation.insert(*v, x);
+                    let mut body_values = vec![];
+                    let value = body.node_values(semantics, &interpretation, &mut body_values)?;
+                    instances.push((value, body_values));
+                }
+                let value = instances.iter()
+                    .map(|(value, _)| *value)
+                    .reduce(|lhs, rhs| semantics.secondary_func(name, lhs, rhs))
+                    .unwrap();
+                let shown = instances.iter().position(|(x, _)| *x == value).unwrap_or(0);
+                values.push(value);
+                values.append(&mut instances.swap_remove(shown).1);
+                Ok(value)
+            },
+        }
+    }
+
+    pub fn three_valued_table(&self, semantics: &(impl Semantics + ?Sized), options: &TableOptions) -> String {
+        let mut out = vec![];
+        self.write_three_valued_table(&mut out, semantics, options).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    // a text table with a row for each of the 3^n interpretations of the n free letters, showing the
+    // labels of the options, unknown included, under every symbol. more than 80 letters are an error. the row order and the filter of the options are applied, with the filter only
+    // keeping rows where the letters and the formula have the known truth values it asks for.
+    pub fn write_three_valued_table(
+        &self,
+        out: &mut dyn Write,
+        semantics: &(impl Semantics + ?Sized),
+        options: &TableOptions,
+    ) -> std::io::Result<()> {
+        let letters: Vec<_> = self.free_letters().into_iter().collect();
+        let row_cnt = 3u128.checked_pow(letters.len() as u32)
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "a three-valued table can have at most 80 letters"))?;
+        // the unknown label may be the widest
+        let labels = &options.labels;
+        let column_width = |symbol: &str| column_width(symbol, labels).max(labels.unknown_label.width());
+        let letter_widths: Vec<_> = letters.iter().map(|c| column_width(&c.to_string())).collect();
+        let sentence = self.to_sentence();
+        let main = self.main_connective_index();
+
+        let mut header = String::new();
+        for (c, width) in letters.iter().zip(&letter_widths) {
+            header.push_str(&cell(&c.to_string(), *width, None, false, false));
+        }
+        header.push('|');
+        header.push(' ');
+        for (j, x) in sentence.iter().enumerate() {
+            let x = x.symbol(options.symbols);
+            header.push_str(&cell(&x, column_width(&x), None, j == main, options.color));
+        }
+        writeln!(out, "{}", header)?;
+
+        let filter = &options.filter;
+        for i in 0..row_cnt {
+            let interpretation: HashMap<_, _> = letters.iter().copied().zip(interpretation(options.order, i, letters.len())).collect();
+            let mut values = vec![];
+            let value = self.node_values(semantics, &interpretation, &mut values).unwrap();
+            let keeps = filter.value.is_none_or(|b| value == b.into())
+                && filter.assignment.iter().all(|(c, b)| interpretation.get(c).is_none_or(|x| *x == (*b).into()));
+            if ! keeps {
+                continue;
+            }
+
+            let mut row = String::new();
+            for (c, width) in letters.iter().zip(&letter_widths) {
+                let x = interpretation[c];
+                row.push_str(&cell(x.label(labels), *width, x.known(), false, options.color));
+            }
+            row.push('|');
+            row.push(' ');
+            let mut values = values.into_iter();
+            for (j, x) in sentence.iter().enumerate() {
+                let width = column_width(&x.symbol(options.symbols));
+                match x {
+                    Alphabet::OpenBracket | Alphabet::CloseBracket => row.push_str(&cell("", width, None, false, options.color)),
+                    _ => {
+                        let value = values.next().unwrap();
+                        row.push_str(&cell(value.label(labels), width, value.known(), j == main, options.color));
+                    },
+                }
+            }
+            writeln!(out, "{}", row)?;
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod three_valued_tests {
+    use super::*;
+    use TruthValue::{False as F, Unknown as U, True as T};
+    use crate::test_util::formula;
+
+    fn eval(logic: Logic, s: &str, interpretation: &[(Var, TruthValue)]) -> TruthValue {
+        eval_with(&logic, s, interpretation)
+    }
+
+    fn eval_with(semantics: &impl Semantics, s: &str, interpretation: &[(Var, TruthValue)]) -> TruthValue {
+        formula(s).eval_three_valued(semantics, &interpretation.iter().copied().collect()).unwrap()
+    }
+
+    #[test]
+    fn connectives() {
+        assert_eq!(eval(Logic::Kleene, "P & Q", &[('P', F), ('Q', U)]), F);
+        assert_eq!(eval(Logic::Bochvar, "P & Q", &[('P', F), ('Q', U)]), U);
+        assert_eq!(eval(Logic::Kleene, "P | ! P", &[('P', U)]), U);
+        assert_eq!(eval(Logic::Kleene, "P -> P", &[('P', U)]), U);
+        assert_eq!(eval(Logic::Lukasiewicz, "P -> P", &[('P', U)]), T);
+        assert_eq!(eval(Logic::Lukasiewicz, "P = Q", &[('P', U), ('Q', T)]), U);
+        assert_eq!(eval(Logic::Lukasiewicz, "P -> Q", &[('P', T), ('Q', U)]), U);
+        assert_eq!(eval(Logic::Kleene, "∃P. P & Q", &[('Q', T)]), T);
+        assert_eq!(eval(Logic::Kleene, "∀P. P | ! P", &[]), U);
+    }
+
+    #[test]
+    fn classical_values_agree() {
+        for s in ["( P -> Q ) = ( ! Q -> ! P )", "( P & ! Q ) | ( t -> P )"] {
+            let f = formula(s);
+            for (values, value) in f.rows() {
+                let interpretation: HashMap<_, _> = ['P', 'Q'].into_iter().zip(values.into_iter().map(TruthValue::from)).collect();
+                for logic in [Logic::Kleene, Logic::Bochvar, Logic::Lukasiewicz] {
+                    assert_eq!(f.eval_three_valued(&logic, &interpretation), Ok(value.into()), "{} in {:?}", s, logic);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn table() {
+        assert_eq!(
+            formula("( P -> Q ) & P").three_valued_table(&Logic::Kleene, &TableOptions::default()),
+            String::from(
+"P Q | ( P → Q ) ∧ P 
+F F |   F T F   F F 
+F U |   F T U   F F 
+F T |   F T T   F F 
+U F |   U U F   U U 
+U U |   U U U   U U 
+U T |   U T T   U U 
+T F |   T F F   F T 
+T U |   T U U   U T 
+T T |   T T T   T T 
+"
+            )
+        );
+    }
+
+    #[test]
+    fn labels_and_too_many_letters() {
+        let options = TableOptions { labels: Labels::numeric(), ..TableOptions::default() };
+        assert_eq!(
+            formula("! P").three_valued_table(&Logic::Kleene, &options),
+            String::from(
+"P | ¬ P 
+0 | 1 0 
+? | ? ? 
+1 | 0 1 
+"
+            )
+        );
+        let options = TableOptions { labels: Labels::with_unknown("T", "F", "unknown"), ..TableOptions::default() };
+        assert!(formula("! P").three_valued_table(&Logic::Kleene, &options).starts_with("P       | ¬       P       \nF       | T       F       \n"));
+
+        let letters: Vec<_> = ('A'..).filter(|c| c.is_uppercase()).take(81).collect();
+        let f = letters[1..].iter().fold(Formula::Letter(letters[0]), |f, c| Formula::SecondaryFunc {
+            name: SecondaryFuncName::Conjunction,
+            lhs: Box::new(f),
+            rhs: Box::new(Formula::Letter(*c)),
+        });
+        let error = f.write_three_valued_table(&mut vec![], &Logic::Kleene, &TableOptions::default()).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn classical() {
+        for s in ["( P -> Q ) = ( ! Q -> ! P )", "( P & ! Q ) | ( t -> P )", "∀P. P | Q"] {
+            let f = formula(s);
+            let letters: Vec<_> = f.free_letters().into_iter().collect();
+            for (values, value) in f.rows() {
+                let interpretation: HashMap<_, _> = letters.iter().copied().zip(values.into_iter().map(TruthValue::from)).collect();
+                assert_eq!(f.eval_three_valued(&Classical, &interpretation), Ok(value.into()), "{}", s);
+            }
+        }
+        assert_eq!(eval_with(&Classical, "P | ! P", &[('P', U)]), U);
+        assert_eq!(eval_with(&Classical, "P & Q", &[('P', F), ('Q', U)]), U);
+    }
+
+    #[test]
+    fn orders() {
+        let rows = |order| (0..9).map(|i| interpretation(order, i, 2)).collect::<Vec<_>>();
+        assert_eq!(rows(RowOrder::TrueFirst)[..3], [vec![T, T], vec![T, U], vec![T, F]]);
+        let gray = rows(RowOrder::Gray);
+        assert_eq!(gray[..4], [vec![F, F], vec![F, U], vec![F, T], vec![U, T]]);
+        for pair in gray.windows(2) {
+            assert_eq!(pair[0].iter().zip(&pair[1]).filter(|(a, b)| a != b).count(), 1);
+        }
+        assert_eq!(Logic::try_from("bochvar"), Ok(Logic::Bochvar));
+        assert!(Logic::try_from("intuitionistic").is_err());
+    }
+}
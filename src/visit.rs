@@ -0,0 +1,249 @@
+use std::collections::BTreeSet;
+use crate::formula::{Formula, SecondaryFuncName, Var};
+
+// a pass looking at every node of a formula.
+// the default methods go on into the subformulas, so an implementation only overrides the nodes it is
+// interested in, and calls walk, or the default method, where it wants the traversal to continue.
+pub trait Visitor<'a> {
+    fn visit(&mut self, f: &'a Formula) {
+        walk(self, f);
+    }
+
+    fn visit_letter(&mut self, _c: Var) {}
+
+    fn visit_true(&mut self) {}
+
+    fn visit_false(&mut self) {}
+
+    fn visit_negation(&mut self, sub: &'a Formula) {
+        self.visit(sub);
+    }
+
+    fn visit_secondary_func(&mut self, _name: SecondaryFuncName, lhs: &'a Formula, rhs: &'a Formula) {
+        self.visit(lhs);
+        self.visit(rhs);
+    }
+
+    fn visit_forall(&mut self, _v: Var, body: &'a Formula) {
+        self.visit(body);
+    }
+
+    fn visit_exists(&mut self, _v: Var, body: &'a Formula) {
+        self.visit(body);
+    }
+}
+
+// call the visitor method for the kind of node f is
+pub fn walk<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, f: &'a Formula) {
+    match f {
+        Formula::Letter(c) => visitor.visit_letter(*c),
+        Formula::True => visitor.visit_true(),
+        Formula::False => visitor.visit_false(),
+        Formula::Negation(sub) => visitor.visit_negation(sub),
+        Formula::SecondaryFunc { name, lhs, rhs } => visitor.visit_secondary_func(*name, lhs, rhs),
+        Formula::Forall(v, body) => visitor.visit_forall(*v, body),
+        Formula::Exists(v, body) => visitor.visit_exists(*v, body),
+    }
+}
+
+// a transform building a new formula from an old one.
+// the default methods rebuild each node from its folded subformulas, so the identity needs no overrides.
+pub trait Fold {
+    fn fold(&mut self, f: &Formula) -> Formula {
+        rebuild(self, f)
+    }
+
+    fn fold_letter(&mut self, c: Var) -> Formula {
+        Formula::Letter(c)
+    }
+
+    fn fold_true(&mut self) -> Formula {
+        Formula::True
+    }
+
+    fn fold_false(&mut self) -> Formula {
+        Formula::False
+    }
+
+    fn fold_negation(&mut self, sub: &Formula) -> Formula {
+        Formula::Negation(Box::new(self.fold(sub)))
+    }
+
+    fn fold_secondary_func(&mut self, name: SecondaryFuncName, lhs: &Formula, rhs: &Formula) -> Formula {
+        Formula::SecondaryFunc { name, lhs: Box::new(self.fold(lhs)), rhs: Box::new(self.fold(rhs)) }
+    }
+
+    fn fold_forall(&mut self, v: Var, body: &Formula) -> Formula {
+        Formula::Forall(v, Box::new(self.fold(body)))
+    }
+
+    fn fold_exists(&mut self, v: Var, body: &Formula) -> Formula {
+        Formula::Exists(v, Box::new(self.fold(body)))
+    }
+}
+
+// call the fold method for the kind of node f is
+pub fn rebuild<F: Fold + ?Sized>(folder: &mut F, f: &Formula) -> Formula {
+    match f {
+        Formula::Letter(c) => folder.fold_letter(*c),
+        Formula::True => folder.fold_true(),
+        Formula::False => folder.fold_false(),
+        Formula::Negation(sub) => folder.fold_negation(sub),
+        Formula::SecondaryFunc { name, lhs, rhs } => folder.fold_secondary_func(*name, lhs, rhs),
+        Formula::Forall(v, body) => folder.fold_forall(*v, body),
+        Formula::Exists(v, body) => folder.fold_exists(*v, body),
+    }
+}
+
+struct Map<F>(F);
+
+impl<F: FnMut(Formula) -> Formula> Fold for Map<F> {
+    fn fold(&mut self, f: &Formula) -> Formula {
+        let f = rebuild(self, f);
+        (self.0)(f)
+    }
+}
+
+struct Subformulas<'a>(Vec<&'a Formula>);
+
+impl<'a> Visitor<'a> for Subformulas<'a> {
+    fn visit(&mut self, f: &'a Formula) {
+        self.0.push(f);
+        walk(self, f);
+    }
+}
+
+struct Size(usize);
+
+impl Visitor<'_> for Size {
+    fn visit(&mut self, f: &Formula) {
+        self.0 += 1;
+        walk(self, f);
+    }
+}
+
+struct Depth {
+    current: usize,
+    max: usize,
+}
+
+impl Visitor<'_> for Depth {
+    fn visit(&mut self, f: &Formula) {
+        self.max = self.max.max(self.current);
+        self.current += 1;
+        walk(self, f);
+        self.current -= 1;
+    }
+}
+
+struct Letters(BTreeSet<Var>);
+
+impl Visitor<'_> for Letters {
+    fn visit_letter(&mut self, c: Var) {
+        self.0.insert(c);
+    }
+
+    fn visit_forall(&mut self, v: Var, body: &Formula) {
+        self.0.insert(v);
+        self.visit(body);
+    }
+
+    fn visit_exists(&mut self, v: Var, body: &Formula) {
+        self.0.insert(v);
+        self.visit(body);
+    }
+}
+
+impl Formula {
+    // apply f to every node, from the letters up, each node having its subformulas mapped already
+    pub fn map(&self, f: impl FnMut(Formula) -> Formula) -> Formula {
+        Map(f).fold(self)
+    }
+
+    // every node, the formula itself first, in the order they are written
+    pub fn subformulas(&self) -> Vec<&Formula> {
+        let mut subformulas = Subformulas(vec![]);
+        subformulas.visit(self);
+        subformulas.0
+    }
+
+    // the number of connectives and quantifiers around the most deeply nested letter or constant
+    pub fn depth(&self) -> usize {
+        let mut depth = Depth { current: 0, max: 0 };
+        depth.visit(self);
+        depth.max
+    }
+
+    // the number of nodes
+    pub fn size(&self) -> usize {
+        let mut size = Size(0);
+        size.visit(self);
+        size.0
+    }
+
+    // every letter in the formula, bound letters included, unlike free_letters
+    pub fn letters(&self) -> BTreeSet<Var> {
+        let mut letters = Letters(BTreeSet::new());
+        letters.visit(self);
+        letters.0
+    }
+}
+
+
+#[cfg(test)]
+mod visit_tests {
+    use super::*;
+    use crate::test_util::formula;
+
+    #[test]
+    fn helpers() {
+        let f = formula("( P -> ! Q ) & ∀R. R");
+        let subformulas: Vec<_> = f.subformulas().into_iter().map(|x| x.to_string()).collect();
+        assert_eq!(subformulas, vec!["(P → ¬ Q) ∧ (∀R. R)", "P → ¬ Q", "P", "¬ Q", "Q", "∀R. R", "R"]);
+        assert_eq!(f.size(), 7);
+        assert_eq!(f.depth(), 3);
+        assert_eq!(formula("P").depth(), 0);
+        assert_eq!(f.letters(), BTreeSet::from(['P', 'Q', 'R']));
+        assert_eq!(f.free_letters(), BTreeSet::from(['P', 'Q']));
+    }
+
+    #[test]
+    fn map_rewrites_from_the_letters_up() {
+        // replace every implication A → B by ¬ A ∨ B
+        let f = formula("( P -> Q ) -> R");
+        let mapped = f.map(|x| match x {
+            Formula::SecondaryFunc { name: SecondaryFuncName::Implicature, lhs, rhs } =>
+                Formula::SecondaryFunc { name: SecondaryFuncName::Disjunction, lhs: Box::new(Formula::Negation(lhs)), rhs },
+            x => x,
+        });
+        assert_eq!(mapped, formula("! ( ! P | Q ) | R"));
+        assert!(mapped.is_equivalent(&f));
+        assert_eq!(f.map(|x| x), f);
+    }
+
+    #[test]
+    fn custom_visitor_and_fold() {
+        // count the negations, without looking inside quantifiers
+        struct Negations(usize);
+        impl Visitor<'_> for Negations {
+            fn visit_negation(&mut self, sub: &Formula) {
+                self.0 += 1;
+                self.visit(sub);
+            }
+            fn visit_forall(&mut self, _v: Var, _body: &Formula) {}
+        }
+        let mut negations = Negations(0);
+        negations.visit(&formula("! ! P & ∀Q. ! Q"));
+        assert_eq!(negations.0, 2);
+
+        // swap the sides of every conjunction
+        struct Swap;
+        impl Fold for Swap {
+            fn fold_secondary_func(&mut self, name: SecondaryFuncName, lhs: &Formula, rhs: &Formula) -> Formula {
+                let (lhs, rhs) = if name == SecondaryFuncName::Conjunction { (rhs, lhs) } else { (lhs, rhs) };
+                Formula::SecondaryFunc { name, lhs: Box::new(self.fold(lhs)), rhs: Box::new(self.fold(rhs)) }
+            }
+        }
+        assert_eq!(Swap.fold(&formula("( P & Q ) | ( R & S )")), formula("( Q & P ) | ( S & R )"));
+    }
+}